@@ -1,4 +1,7 @@
-include!("calc_parser.rs");
+use generator_macros::grammar_file;
+
+#[grammar_file("examples/calculator/calc.l1l")]
+mod parser {}
 
 fn main() {
     for arg in std::env::args().skip(1) {
@@ -1,3 +1,5 @@
+use generator_macros::grammar_file;
+
 use crate::tree_visualizer::Paint;
 
 mod tree_visualizer {
@@ -158,7 +160,8 @@ mod ast {
     }
 }
 
-include!("kotlin_parser.rs");
+#[grammar_file("examples/kotlin/kotlin.l1l")]
+mod parser {}
 
 fn main() {
     let source = "fun foo(a:Int,b:Double):Double";
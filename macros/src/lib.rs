@@ -0,0 +1,217 @@
+use std::{env, fs, path::PathBuf};
+
+use generator::diagnostics::{Diagnostics, Severity};
+use proc_macro2::{Delimiter, Span, TokenStream, TokenTree};
+use quote::{quote, quote_spanned};
+use syn::{parse_macro_input, AttrStyle, ItemMod, LitStr};
+
+struct Source {
+    text: String,
+    spans: Vec<(usize, Span)>,
+}
+
+impl Source {
+    fn push(&mut self, text: &str, span: Span, space: bool) {
+        self.spans.push((self.text.len(), span));
+        self.text.push_str(text);
+
+        if space {
+            self.text.push(' ');
+        }
+    }
+
+    fn span_at(&self, offset: usize) -> Span {
+        self.spans
+            .iter()
+            .take_while(|(start, _)| *start <= offset)
+            .last()
+            .map_or_else(Span::call_site, |(_, span)| *span)
+    }
+
+    fn write_stream(&mut self, stream: TokenStream) {
+        let tokens: Vec<_> = stream.into_iter().collect();
+        let mut i = 0;
+
+        while i < tokens.len() {
+            match (&tokens[i], tokens.get(i + 1), tokens.get(i + 2)) {
+                (
+                    TokenTree::Punct(open),
+                    Some(TokenTree::Group(code)),
+                    Some(TokenTree::Punct(close)),
+                ) if open.as_char() == '!'
+                    && close.as_char() == '!'
+                    && code.delimiter() == Delimiter::Brace =>
+                {
+                    self.push(&format!("!{{ {} }}!", code.stream()), code.span(), true);
+                    i += 3;
+                    continue;
+                }
+                (TokenTree::Group(group), ..) => {
+                    let (open, close) = match group.delimiter() {
+                        Delimiter::Parenthesis => ("(", ")"),
+                        Delimiter::Brace => ("{", "}"),
+                        Delimiter::Bracket => ("[", "]"),
+                        Delimiter::None => ("", ""),
+                    };
+
+                    self.push(open, group.span_open(), true);
+                    self.write_stream(group.stream());
+                    self.push(close, group.span_close(), true);
+                }
+                (TokenTree::Ident(ident), ..) => self.push(&ident.to_string(), ident.span(), true),
+                (TokenTree::Punct(punct), ..) => self.push(
+                    &punct.as_char().to_string(),
+                    punct.span(),
+                    punct.spacing() == proc_macro2::Spacing::Alone,
+                ),
+                (TokenTree::Literal(lit), ..) => {
+                    let text = match syn::parse2::<LitStr>(TokenTree::Literal(lit.clone()).into()) {
+                        Ok(lit) => format!("\"{}\"", lit.value().replace('"', "\\\"")),
+                        Err(_) => lit.to_string(),
+                    };
+
+                    self.push(&text, lit.span(), true);
+                }
+            }
+
+            i += 1;
+        }
+    }
+}
+
+fn compile_errors(
+    diagnostics: &Diagnostics,
+    span_at: impl Fn(usize) -> Span,
+    fallback: Span,
+    prefix: impl Fn(usize) -> String,
+) -> TokenStream {
+    diagnostics
+        .iter()
+        .filter(|diagnostic| diagnostic.severity() == Severity::Error)
+        .map(|diagnostic| {
+            let (span, message) = match diagnostic.span() {
+                Some(span) => (
+                    span_at(span.start),
+                    format!("{}{diagnostic}", prefix(span.start)),
+                ),
+                None => (fallback, diagnostic.to_string()),
+            };
+
+            quote_spanned! { span => compile_error!(#message); }
+        })
+        .collect()
+}
+
+/// Expands a grammar written inline into a `parser` module.
+///
+/// ```
+/// generator_macros::grammar! {
+///     preamble = !{ }!
+///     tokens = { "a" = token "a", }
+///     rules = {
+///         pub count: !{ usize }! = {
+///             => !{ 0 }!,
+///             "a" <rest:count> => !{ rest + 1 }!,
+///         }
+///     }
+/// }
+///
+/// let mut state = parser::ParserState::new("a a a");
+/// assert_eq!(parser::parse_count(&mut state).unwrap(), 3);
+/// ```
+#[proc_macro]
+pub fn grammar(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let mut source = Source {
+        text: String::new(),
+        spans: Vec::new(),
+    };
+    source.write_stream(input.into());
+
+    match generator::compile(&source.text) {
+        Ok(tokens) => tokens.into(),
+        Err(diagnostics) => compile_errors(
+            &diagnostics,
+            |offset| source.span_at(offset),
+            Span::call_site(),
+            |_| String::new(),
+        )
+        .into(),
+    }
+}
+
+/// Expands the grammar at a path relative to the crate root into the
+/// annotated inline module, next to the items already in it.
+///
+/// The path is an argument, `#[grammar_file("src/calc.l1l")]`, because
+/// rustc rejects key-value attribute macros such as `#[grammar = "..."]`.
+#[proc_macro_attribute]
+pub fn grammar_file(
+    attr: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(attr as LitStr);
+    let module = parse_macro_input!(item as ItemMod);
+
+    match expand_grammar_file(&path, module) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.into_compile_error().into(),
+    }
+}
+
+fn expand_grammar_file(path: &LitStr, module: ItemMod) -> syn::Result<TokenStream> {
+    let Some((_, content)) = module.content else {
+        return Err(syn::Error::new_spanned(
+            module.ident,
+            "expected an inline module: `mod name {}`",
+        ));
+    };
+
+    let full_path = match env::var("CARGO_MANIFEST_DIR") {
+        Ok(dir) => PathBuf::from(dir).join(path.value()),
+        Err(_) => PathBuf::from(path.value()),
+    };
+    let display = full_path.display().to_string();
+
+    let source = fs::read_to_string(&full_path)
+        .map_err(|e| syn::Error::new(path.span(), format!("failed to read {display}: {e}")))?;
+
+    let language = generator::parse_language(&source).and_then(|language| {
+        language.check()?;
+        Ok(language)
+    });
+
+    let language = match language {
+        Ok(language) => language,
+        Err(diagnostics) => {
+            return Ok(compile_errors(
+                &diagnostics,
+                |_| path.span(),
+                path.span(),
+                |offset| {
+                    let (line, col) = generator::diagnostics::line_col(&source, offset);
+                    format!("{display}:{line}:{col}: ")
+                },
+            ));
+        }
+    };
+
+    let (inner_attrs, outer_attrs): (Vec<_>, Vec<_>) = module
+        .attrs
+        .into_iter()
+        .partition(|attr| matches!(attr.style, AttrStyle::Inner(_)));
+    let vis = module.vis;
+    let ident = module.ident;
+    let items = language.generate_items();
+
+    Ok(quote! {
+        #(#outer_attrs)*
+        #vis mod #ident {
+            #(#inner_attrs)*
+            #items
+
+            const _: &str = include_str!(#display);
+
+            #(#content)*
+        }
+    })
+}
@@ -1,14 +1,29 @@
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{BTreeMap, HashMap, HashSet},
     fmt,
     hash::Hash,
     str::FromStr,
 };
 
-use proc_macro2::{Ident, Span, TokenStream};
+use proc_macro2::{Ident, TokenStream};
 use quote::quote;
 
-use crate::lexer::{Term, Tokens};
+use crate::{
+    diagnostics::{Diagnostic, Diagnostics},
+    lexer::{Term, Tokens},
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn contains(&self, offset: usize) -> bool {
+        self.start <= offset && offset <= self.end
+    }
+}
 
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct NonTerm(pub String);
@@ -19,8 +34,162 @@ pub enum Node {
         node: NonTerm,
         extract_name: Option<String>,
         args: Option<String>,
+        span: Span,
     },
-    Term(Term, Option<String>),
+    Term(Term, Option<String>, Span),
+}
+
+impl Node {
+    pub fn named(self, name: Option<String>) -> Node {
+        match self {
+            Node::NonTerm {
+                node, args, span, ..
+            } => Node::NonTerm {
+                node,
+                extract_name: name,
+                args,
+                span,
+            },
+            Node::Term(term, _, span) => Node::Term(term, name, span),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Node::NonTerm { span, .. } => *span,
+            Node::Term(_, _, span) => *span,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Repeat {
+    Optional,
+    Star,
+    Plus,
+}
+
+impl Repeat {
+    pub fn suffix(self) -> &'static str {
+        match self {
+            Repeat::Optional => "?",
+            Repeat::Star => "*",
+            Repeat::Plus => "+",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Item {
+    Node(Node),
+    Group {
+        alts: Vec<Vec<Item>>,
+        extract_name: Option<String>,
+        span: Span,
+    },
+    Repeat {
+        item: Box<Item>,
+        repeat: Repeat,
+        extract_name: Option<String>,
+        span: Span,
+    },
+    Macro {
+        name: NonTerm,
+        args: Vec<Item>,
+        extract_name: Option<String>,
+        span: Span,
+        /// The nonterminal the call was expanded into, filled in by desugaring.
+        instance: Option<NonTerm>,
+    },
+}
+
+impl Item {
+    pub fn named(self, name: Option<String>) -> Item {
+        match self {
+            Item::Node(node) => Item::Node(node.named(name)),
+            Item::Group { alts, span, .. } => Item::Group {
+                alts,
+                extract_name: name,
+                span,
+            },
+            Item::Repeat {
+                item, repeat, span, ..
+            } => Item::Repeat {
+                item,
+                repeat,
+                extract_name: name,
+                span,
+            },
+            Item::Macro {
+                name: macro_name,
+                args,
+                span,
+                instance,
+                ..
+            } => Item::Macro {
+                name: macro_name,
+                args,
+                extract_name: name,
+                span,
+                instance,
+            },
+        }
+    }
+
+    pub fn extract_name(&self) -> Option<&String> {
+        match self {
+            Item::Node(Node::NonTerm { extract_name, .. })
+            | Item::Node(Node::Term(_, extract_name, _))
+            | Item::Group { extract_name, .. }
+            | Item::Repeat { extract_name, .. }
+            | Item::Macro { extract_name, .. } => extract_name.as_ref(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Item::Node(node) => node.span(),
+            Item::Group { span, .. } | Item::Repeat { span, .. } | Item::Macro { span, .. } => {
+                *span
+            }
+        }
+    }
+
+    fn instances<'a>(&'a self, out: &mut HashSet<&'a NonTerm>) {
+        match self {
+            Item::Node(_) => {}
+            Item::Group { alts, .. } => {
+                for item in alts.iter().flatten() {
+                    item.instances(out);
+                }
+            }
+            Item::Repeat { item, .. } => item.instances(out),
+            Item::Macro { args, instance, .. } => {
+                out.extend(instance);
+
+                for item in args.iter() {
+                    item.instances(out);
+                }
+            }
+        }
+    }
+
+    fn written<'a>(&'a self, out: &mut Vec<&'a Node>) {
+        match self {
+            Item::Node(node) => out.push(node),
+            Item::Group { alts, .. } => {
+                for item in alts.iter().flatten() {
+                    item.written(out);
+                }
+            }
+            Item::Repeat { item, .. } => item.written(out),
+            Item::Macro { args, .. } => {
+                for item in args.iter() {
+                    item.written(out);
+                }
+            }
+        }
+    }
 }
 
 pub struct Display<T>(pub T);
@@ -30,7 +199,7 @@ impl fmt::Display for Display<&'_ [Node]> {
         for node in self.0 {
             match node {
                 Node::NonTerm { node, .. } => write!(f, "{} ", node.0)?,
-                Node::Term(term, _) => write!(f, "\"{} \"", term.0)?,
+                Node::Term(term, _, _) => write!(f, "\"{}\" ", term.0)?,
             }
         }
 
@@ -38,10 +207,101 @@ impl fmt::Display for Display<&'_ [Node]> {
     }
 }
 
+impl fmt::Display for Display<&'_ Item> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            Item::Node(Node::NonTerm { node, .. }) => write!(f, "{}", node.0),
+            Item::Node(Node::Term(term, _, _)) => write!(f, "\"{}\"", term.0),
+            Item::Group { alts, .. } => {
+                let alts: Vec<_> = alts
+                    .iter()
+                    .map(|alt| Display(&alt[..]).to_string())
+                    .collect();
+                write!(f, "({})", alts.join(" | "))
+            }
+            Item::Repeat { item, repeat, .. } => {
+                write!(f, "{}{}", Display(&**item), repeat.suffix())
+            }
+            Item::Macro { name, args, .. } => {
+                let args: Vec<_> = args.iter().map(|arg| Display(arg).to_string()).collect();
+                write!(f, "{}<{}>", name.0, args.join(", "))
+            }
+        }
+    }
+}
+
+impl fmt::Display for Display<&'_ [Item]> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let items: Vec<_> = self
+            .0
+            .iter()
+            .map(|item| Display(item).to_string())
+            .collect();
+        write!(f, "{}", items.join(" "))
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Rule {
     pub nodes: Vec<Node>,
+    pub items: Option<Vec<Item>>,
     pub code: String,
+    pub span: Span,
+}
+
+impl Rule {
+    pub fn new(items: Vec<Item>, code: String, span: Span) -> Rule {
+        if items.iter().all(|item| matches!(item, Item::Node(_))) {
+            let nodes = items
+                .into_iter()
+                .filter_map(|item| match item {
+                    Item::Node(node) => Some(node),
+                    Item::Group { .. } | Item::Repeat { .. } | Item::Macro { .. } => None,
+                })
+                .collect();
+
+            Rule {
+                nodes,
+                items: None,
+                code,
+                span,
+            }
+        } else {
+            Rule {
+                nodes: Vec::new(),
+                items: Some(items),
+                code,
+                span,
+            }
+        }
+    }
+
+    pub fn arm(&self) -> String {
+        let arm = match &self.items {
+            Some(items) => Display(&items[..]).to_string(),
+            None => Display(&self.nodes[..]).to_string(),
+        };
+
+        match arm.trim_end() {
+            "" => "ε".to_owned(),
+            arm => arm.to_owned(),
+        }
+    }
+
+    pub fn written(&self) -> Vec<&Node> {
+        match &self.items {
+            Some(items) => {
+                let mut nodes = Vec::new();
+
+                for item in items.iter() {
+                    item.written(&mut nodes);
+                }
+
+                nodes
+            }
+            None => self.nodes.iter().collect(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -51,21 +311,80 @@ pub struct NonTermDef {
     pub ret_ty: String,
     pub rules: Vec<Rule>,
     pub is_pub: bool,
+    pub helper: Option<Origin>,
+    pub span: Span,
+    pub name_span: Span,
+}
+
+/// The written item a helper nonterminal was desugared from, and the
+/// nonterminal or macro whose rules contain it.
+#[derive(Debug, Clone)]
+pub struct Origin {
+    pub nonterm: NonTerm,
+    pub item: String,
+}
+
+impl NonTermDef {
+    pub fn label(&self, r: usize) -> String {
+        format!("{} {}: {}", self.name.0, r + 1, self.rules[r].arm())
+    }
+
+    pub fn owner(&self) -> &NonTerm {
+        self.helper
+            .as_ref()
+            .map_or(&self.name, |origin| &origin.nonterm)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MacroDef {
+    pub params: Vec<String>,
+    pub def: NonTermDef,
 }
 
 #[derive(Debug, Clone)]
 pub struct Grammar {
     pub nonterms: Vec<NonTermDef>,
+    pub macros: Vec<MacroDef>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub enum Expect {
+    Accept,
+    Reject,
+    Value(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct TestCase {
+    pub input: String,
+    pub expect: Expect,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct TestGroup {
+    pub nonterm: NonTerm,
+    pub cases: Vec<TestCase>,
+    pub span: Span,
+    pub name_span: Span,
+}
+
+#[derive(Debug, Clone)]
+pub struct Tests {
+    pub groups: Vec<TestGroup>,
+    pub span: Span,
 }
 
-type FirstSet = HashSet<Option<Term>>;
-type FirstMap = HashMap<NonTerm, FirstSet>;
+pub type FirstSet = HashSet<Option<Term>>;
+pub type FirstMap = HashMap<NonTerm, FirstSet>;
 
-fn first(nodes: &[Node], fst: &FirstMap) -> FirstSet {
-    match nodes.get(0) {
-        Some(Node::Term(tok, _)) => HashSet::from([Some(tok.clone())]),
+pub fn first(nodes: &[Node], fst: &FirstMap) -> FirstSet {
+    match nodes.first() {
+        Some(Node::Term(tok, _, _)) => HashSet::from([Some(tok.clone())]),
         Some(Node::NonTerm { node: nt, .. }) => {
-            let mut pref = fst[nt].clone();
+            let mut pref = fst.get(nt).cloned().unwrap_or_default();
 
             if pref.contains(&None) {
                 pref.remove(&None);
@@ -80,8 +399,23 @@ fn first(nodes: &[Node], fst: &FirstMap) -> FirstSet {
     }
 }
 
-type FollowSet = HashSet<Option<Term>>;
-type FollowMap = HashMap<NonTerm, FollowSet>;
+pub type FollowSet = HashSet<Option<Term>>;
+pub type FollowMap = HashMap<NonTerm, FollowSet>;
+
+pub fn predict(name: &NonTerm, nodes: &[Node], fst: &FirstMap, flw: &FollowMap) -> FirstSet {
+    let first_terms = first(nodes, fst);
+    let mut terms: FirstSet = first_terms
+        .iter()
+        .filter(|t| t.is_some())
+        .cloned()
+        .collect();
+
+    if first_terms.contains(&None) {
+        terms.extend(flw.get(name).into_iter().flatten().cloned());
+    }
+
+    terms
+}
 
 fn code_or_empty(code: &Option<String>) -> TokenStream {
     if let Some(code) = code {
@@ -92,7 +426,7 @@ fn code_or_empty(code: &Option<String>) -> TokenStream {
 }
 
 impl Grammar {
-    fn build_first(&self) -> FirstMap {
+    pub fn build_first(&self) -> FirstMap {
         let mut changed = true;
         let mut fst: FirstMap = HashMap::new();
 
@@ -120,7 +454,7 @@ impl Grammar {
         fst
     }
 
-    fn build_follow(&self, fst: &FirstMap) -> FollowMap {
+    pub fn build_follow(&self, fst: &FirstMap) -> FollowMap {
         let mut changed = true;
         let mut flw: FollowMap = HashMap::new();
 
@@ -145,14 +479,14 @@ impl Grammar {
                 })
                 .filter_map(|(name, node, rest)| match node {
                     Node::NonTerm { node, .. } => Some((name, node, rest)),
-                    Node::Term(_, _) => None,
+                    Node::Term(_, _, _) => None,
                 });
 
             for (name, node, rest) in pairs {
                 let fst = first(rest, fst);
 
                 let to_add: FollowSet = if fst.contains(&None) {
-                    flw.get(&name)
+                    flw.get(name)
                         .into_iter()
                         .flatten()
                         .cloned()
@@ -164,7 +498,7 @@ impl Grammar {
 
                 match flw.get_mut(node) {
                     Some(v) => {
-                        changed |= to_add.difference(&v).count() > 0;
+                        changed |= to_add.difference(v).count() > 0;
                         v.extend(to_add);
                     }
                     None => {
@@ -180,7 +514,7 @@ impl Grammar {
 }
 
 fn get_fn_name(nt: &str) -> Ident {
-    Ident::new(&format!("parse_{}", nt), Span::call_site())
+    Ident::new(&format!("parse_{}", nt), proc_macro2::Span::call_site())
 }
 
 impl NonTermDef {
@@ -193,21 +527,15 @@ impl NonTermDef {
         let get_name = |name: &Option<String>| {
             Ident::new(
                 name.as_ref().map(|x| &x[..]).unwrap_or("__"),
-                Span::call_site(),
+                proc_macro2::Span::call_site(),
             )
         };
 
         let branches = self.rules.iter().map(|rule| {
-            let first_terms = first(&rule.nodes, fst);
-            let mut terms: Vec<_> = first_terms
-                .clone()
+            let mut terms: Vec<_> = predict(&self.name, &rule.nodes, fst, flw)
                 .into_iter()
-                .filter(|nt| nt.is_some())
                 .collect();
-
-            if first_terms.contains(&None) {
-                terms.extend(flw.get(&self.name).into_iter().flatten().cloned());
-            }
+            terms.sort();
 
             // eprintln!("{:?} -> {:?}, marker: {:?}", self.name, rule, terms);
 
@@ -222,13 +550,14 @@ impl NonTermDef {
                     node: nt,
                     extract_name,
                     args,
+                    ..
                 } => {
                     let name = get_name(extract_name);
                     let nt = get_fn_name(&nt.0);
                     let args = code_or_empty(args);
                     quote! { let #name = #nt (parser, #args)?; }
                 }
-                Node::Term(t, name) => {
+                Node::Term(t, name, _) => {
                     let name = get_name(name);
                     let t = get_fn_name(&t.token_name());
                     quote! { let #name = #t (parser)?; }
@@ -261,7 +590,7 @@ impl NonTermDef {
         };
 
         quote! {
-            #vis fn #fn_name (parser: &mut ParserState, #args) -> Result<#ret_ty, ParseError<Token>> {
+            #vis fn #fn_name<'input>(parser: &mut ParserState<'input>, #args) -> Result<#ret_ty, ParseError<Token>> {
                 match parser.token() {
                     #(#branches)*
                     _ => Err(ParseError::NoRuleFound(#name)),
@@ -286,8 +615,20 @@ impl Grammar {
     }
 
     pub fn check_ll1(&self) -> Result<(), (NonTerm, &[Node], &[Node])> {
+        match self.conflicts().into_iter().next() {
+            Some(conflict) => Err((
+                conflict.nonterm,
+                &conflict.arms.0.nodes[..],
+                &conflict.arms.1.nodes[..],
+            )),
+            None => Ok(()),
+        }
+    }
+
+    pub fn conflicts(&self) -> Vec<Conflict<'_>> {
         let fst = self.build_first();
         let flw = self.build_follow(&fst);
+        let mut conflicts = Vec::new();
 
         for def in self.nonterms.iter() {
             let pairs = def
@@ -300,7 +641,7 @@ impl Grammar {
                 let fst1 = first(&r1.nodes, &fst);
                 let fst2 = first(&r2.nodes, &fst);
                 let flw_def = {
-                    let mut flw_a = flw.get(&def.name).unwrap().clone();
+                    let mut flw_a = flw.get(&def.name).cloned().unwrap_or_default();
                     flw_a.remove(&None);
                     flw_a
                 };
@@ -309,44 +650,495 @@ impl Grammar {
                     || (fst1.contains(&None) && fst2.intersection(&flw_def).count() != 0)
                     || (fst2.contains(&None) && fst1.intersection(&flw_def).count() != 0)
                 {
-                    return Err((def.name.clone(), &r1.nodes[..], &r2.nodes[..]));
+                    let prd1 = predict(&def.name, &r1.nodes, &fst, &flw);
+                    let prd2 = predict(&def.name, &r2.nodes, &fst, &flw);
+                    let mut tokens: Vec<_> = prd1.intersection(&prd2).cloned().collect();
+                    tokens.sort();
+
+                    conflicts.push(Conflict {
+                        nonterm: def.owner().clone(),
+                        item: def.helper.as_ref().map(|origin| origin.item.clone()),
+                        arms: (r1, r2),
+                        tokens,
+                    });
                 }
             }
         }
 
-        Ok(())
+        conflicts
+    }
+}
+
+pub type PredictTable = HashMap<NonTerm, BTreeMap<Option<Term>, Vec<usize>>>;
+
+impl Grammar {
+    pub fn predict_table(&self) -> PredictTable {
+        let fst = self.build_first();
+        let flw = self.build_follow(&fst);
+        let mut table = PredictTable::new();
+
+        for def in self.nonterms.iter() {
+            let row = table.entry(def.name.clone()).or_default();
+
+            for (i, rule) in def.rules.iter().enumerate() {
+                for term in predict(&def.name, &rule.nodes, &fst, &flw) {
+                    row.entry(term).or_default().push(i);
+                }
+            }
+        }
+
+        table
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct Conflict<'g> {
+    pub nonterm: NonTerm,
+    pub item: Option<String>,
+    pub arms: (&'g Rule, &'g Rule),
+    pub tokens: Vec<Option<Term>>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Language {
     pub preamble: String,
     pub lexer: Tokens,
     pub grammar: Grammar,
+    pub tests: Option<Tests>,
+}
+
+fn check_code(
+    code: &str,
+    span: Option<Span>,
+    context: impl FnOnce() -> String,
+    diagnostics: &mut Diagnostics,
+) {
+    if let Err(e) = TokenStream::from_str(code) {
+        diagnostics.push(Diagnostic::InvalidCode {
+            context: context(),
+            message: e.to_string(),
+            span,
+        });
+    }
+}
+
+impl Grammar {
+    /// The nonterminals as written: user definitions and macro instances,
+    /// leaving out the helpers desugaring made for groups and repetitions.
+    pub fn written(&self) -> Vec<&NonTermDef> {
+        let mut instances = HashSet::new();
+
+        for items in self
+            .nonterms
+            .iter()
+            .flat_map(|def| def.rules.iter())
+            .filter_map(|rule| rule.items.as_ref())
+        {
+            for item in items.iter() {
+                item.instances(&mut instances);
+            }
+        }
+
+        self.nonterms
+            .iter()
+            .filter(|def| def.helper.is_none() || instances.contains(&def.name))
+            .collect()
+    }
+
+    /// The written alternative that alternative `r` of `def` belongs to.
+    /// Desugaring gives helper rules the span of the rule using them, which
+    /// is how a helper alternative is traced back.
+    pub fn owning_rule<'g>(&'g self, def: &'g NonTermDef, r: usize) -> (&'g NonTermDef, usize) {
+        let written = self.written();
+
+        if written.iter().any(|w| w.name == def.name) {
+            return (def, r);
+        }
+
+        let span = def.rules[r].span;
+
+        written
+            .into_iter()
+            .find_map(|w| {
+                w.rules
+                    .iter()
+                    .position(|rule| rule.span == span)
+                    .map(|o| (w, o))
+            })
+            .unwrap_or((def, r))
+    }
+
+    /// Names alternative `r` of `def` as written: helper alternatives are
+    /// labelled by the written alternative and item they come from.
+    pub fn label(&self, def: &NonTermDef, r: usize) -> String {
+        let (owner, o) = self.owning_rule(def, r);
+
+        match &def.helper {
+            Some(origin) if owner.name != def.name => format!(
+                "{} {}: {} {}: {}",
+                owner.name.0,
+                o + 1,
+                origin.item,
+                r + 1,
+                def.rules[r].arm()
+            ),
+            _ => def.label(r),
+        }
+    }
+
+    pub fn reachable(&self) -> HashSet<&NonTerm> {
+        let defs: HashMap<_, _> = self.nonterms.iter().map(|def| (&def.name, def)).collect();
+        let mut stack: Vec<_> = self
+            .nonterms
+            .iter()
+            .filter(|def| def.is_pub)
+            .map(|def| &def.name)
+            .collect();
+        let mut reachable: HashSet<_> = stack.iter().copied().collect();
+
+        while let Some(name) = stack.pop() {
+            let Some(def) = defs.get(name) else {
+                continue;
+            };
+
+            for rule in def.rules.iter() {
+                for node in rule.nodes.iter() {
+                    if let Node::NonTerm { node, .. } = node {
+                        if reachable.insert(node) {
+                            stack.push(node);
+                        }
+                    }
+                }
+            }
+        }
+
+        reachable
+    }
+
+    pub fn nullable(&self) -> HashSet<&NonTerm> {
+        let fst = self.build_first();
+
+        self.nonterms
+            .iter()
+            .map(|def| &def.name)
+            .filter(|name| fst.get(*name).is_some_and(|set| set.contains(&None)))
+            .collect()
+    }
+
+    pub fn left_corners(&self) -> HashMap<&NonTerm, HashSet<&NonTerm>> {
+        let fst = self.build_first();
+        let nullable = |node: &Node| match node {
+            Node::NonTerm { node, .. } => fst.get(node).is_some_and(|set| set.contains(&None)),
+            Node::Term(..) => false,
+        };
+
+        let direct: HashMap<_, HashSet<_>> = self
+            .nonterms
+            .iter()
+            .map(|def| {
+                let mut corners = HashSet::new();
+
+                for rule in def.rules.iter() {
+                    for node in rule.nodes.iter() {
+                        if let Node::NonTerm { node: name, .. } = node {
+                            corners.insert(name);
+                        }
+
+                        if !nullable(node) {
+                            break;
+                        }
+                    }
+                }
+
+                (&def.name, corners)
+            })
+            .collect();
+
+        direct
+            .keys()
+            .map(|&name| {
+                let mut stack: Vec<_> = direct[name].iter().copied().collect();
+                let mut corners = HashSet::new();
+
+                while let Some(next) = stack.pop() {
+                    if corners.insert(next) {
+                        stack.extend(direct.get(next).into_iter().flatten().copied());
+                    }
+                }
+
+                (name, corners)
+            })
+            .collect()
+    }
+
+    pub fn left_recursive(&self) -> HashSet<&NonTerm> {
+        self.left_corners()
+            .into_iter()
+            .filter(|(name, corners)| corners.contains(name))
+            .map(|(name, _)| name)
+            .collect()
+    }
 }
 
 impl Language {
+    pub fn diagnostics(&self) -> Diagnostics {
+        let mut diagnostics = Diagnostics::default();
+        let terms: HashSet<_> = self.lexer.mapping.iter().map(|(term, _, _)| term).collect();
+        let mut defined = HashSet::new();
+
+        for def in self.grammar.nonterms.iter() {
+            if !defined.insert(&def.name) {
+                diagnostics.push(Diagnostic::DuplicateNonTerm {
+                    nonterm: def.name.clone(),
+                    span: def.name_span,
+                });
+            }
+        }
+
+        check_code(
+            &self.preamble,
+            None,
+            || "preamble".to_owned(),
+            &mut diagnostics,
+        );
+
+        for def in self.grammar.nonterms.iter() {
+            let name = &def.name.0;
+            let def_span = Some(def.name_span);
+
+            if let Some(args) = &def.args {
+                check_code(
+                    args,
+                    def_span,
+                    || format!("arguments of {name}"),
+                    &mut diagnostics,
+                );
+            }
+
+            check_code(
+                &def.ret_ty,
+                def_span,
+                || format!("return type of {name}"),
+                &mut diagnostics,
+            );
+
+            for rule in def.rules.iter() {
+                check_code(
+                    &rule.code,
+                    Some(rule.span),
+                    || format!("action in rules for {name}"),
+                    &mut diagnostics,
+                );
+
+                for node in rule.nodes.iter() {
+                    match node {
+                        Node::NonTerm {
+                            node, args, span, ..
+                        } => {
+                            if !defined.contains(node) {
+                                diagnostics.push(Diagnostic::UndefinedNonTerm {
+                                    nonterm: node.clone(),
+                                    used_in: def.owner().clone(),
+                                    span: *span,
+                                });
+                            }
+
+                            if let Some(args) = args {
+                                check_code(
+                                    args,
+                                    Some(*span),
+                                    || format!("arguments of {} in rules for {name}", node.0),
+                                    &mut diagnostics,
+                                );
+                            }
+                        }
+                        Node::Term(term, _, span) => {
+                            if !terms.contains(term) {
+                                diagnostics.push(Diagnostic::UndefinedTerm {
+                                    term: term.clone(),
+                                    used_in: def.owner().clone(),
+                                    span: *span,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for group in self.tests.iter().flat_map(|tests| tests.groups.iter()) {
+            let name = &group.nonterm.0;
+            let Some(def) = self
+                .grammar
+                .nonterms
+                .iter()
+                .find(|def| def.name == group.nonterm)
+            else {
+                diagnostics.push(Diagnostic::InvalidTest {
+                    nonterm: group.nonterm.clone(),
+                    message: format!("tests for undefined nonterminal {name}"),
+                    span: group.name_span,
+                });
+                continue;
+            };
+
+            for case in group.cases.iter() {
+                let Expect::Value(code) = &case.expect else {
+                    continue;
+                };
+
+                check_code(
+                    code,
+                    Some(case.span),
+                    || format!("expected value of test for {name}"),
+                    &mut diagnostics,
+                );
+
+                if def.args.is_some() {
+                    diagnostics.push(Diagnostic::InvalidTest {
+                        nonterm: group.nonterm.clone(),
+                        message: format!(
+                            "expected values cannot be checked for {name}, which takes arguments"
+                        ),
+                        span: case.span,
+                    });
+                }
+            }
+        }
+
+        for conflict in self.grammar.conflicts() {
+            diagnostics.push(conflict);
+        }
+
+        if self.grammar.nonterms.iter().any(|def| def.is_pub) {
+            let reachable = self.grammar.reachable();
+
+            for def in self.grammar.nonterms.iter() {
+                if def.helper.is_none() && !reachable.contains(&def.name) {
+                    diagnostics.push(Diagnostic::Unreachable {
+                        nonterm: def.name.clone(),
+                        span: def.name_span,
+                    });
+                }
+            }
+        }
+
+        diagnostics
+    }
+
+    pub fn check(&self) -> Result<(), Diagnostics> {
+        let diagnostics = self.diagnostics();
+
+        if diagnostics.has_errors() {
+            Err(diagnostics)
+        } else {
+            Ok(())
+        }
+    }
+
     pub fn generate(&self) -> TokenStream {
+        self.generate_module("parser")
+    }
+
+    pub fn generate_module(&self, name: &str) -> TokenStream {
+        let name = Ident::new(name, proc_macro2::Span::call_site());
+        let items = self.generate_items();
+
+        quote! {
+            mod #name {
+                #items
+            }
+        }
+    }
+
+    fn generate_tests(&self) -> TokenStream {
+        let mut counts: HashMap<&NonTerm, usize> = HashMap::new();
+        let mut tests = Vec::new();
+
+        for group in self.tests.iter().flat_map(|tests| tests.groups.iter()) {
+            let takes_args = self
+                .grammar
+                .nonterms
+                .iter()
+                .any(|def| def.name == group.nonterm && def.args.is_some());
+
+            for case in group.cases.iter() {
+                let count = counts.entry(&group.nonterm).or_default();
+                *count += 1;
+
+                let Expect::Value(code) = &case.expect else {
+                    continue;
+                };
+
+                if takes_args {
+                    continue;
+                }
+
+                let name = Ident::new(
+                    &format!("{}_{count}", group.nonterm.0),
+                    proc_macro2::Span::call_site(),
+                );
+                let parse_fn = get_fn_name(&group.nonterm.0);
+                let input = &case.input;
+                let code = TokenStream::from_str(code).unwrap();
+
+                tests.push(quote! {
+                    #[test]
+                    fn #name() {
+                        let mut parser = ParserState::new(#input);
+                        let value = match #parse_fn(&mut parser) {
+                            Ok(value) => value,
+                            Err(e) => panic!("{:?} does not parse: {}", #input, e),
+                        };
+
+                        parser.push_spaces();
+                        assert_eq!(parser.remainder(), "", "{:?} is not consumed entirely", #input);
+                        assert_eq!(value, { #code });
+                    }
+                });
+            }
+        }
+
+        if tests.is_empty() {
+            return quote! {};
+        }
+
+        quote! {
+            #[cfg(test)]
+            mod tests {
+                use super::*;
+
+                #(#tests)*
+            }
+        }
+    }
+
+    pub fn generate_items(&self) -> TokenStream {
         let inner: TokenStream = [
             TokenStream::from_str(include_str!("general.rs")).unwrap(),
             TokenStream::from_str(&self.preamble).unwrap(),
             self.lexer.generate(),
             self.grammar.generate(),
+            self.generate_tests(),
         ]
         .into_iter()
         .collect();
 
         quote! {
-            mod parser {
-                #![allow(non_camel_case_types)]
-                #![allow(non_upper_case_globals)]
-                #![allow(dead_code)]
-                #![allow(non_snake_case)]
-                #![allow(unused_braces)]
-                #![allow(unreachable_patterns)]
-
-                #inner
-            }
+            #![allow(non_camel_case_types)]
+            #![allow(non_upper_case_globals)]
+            #![allow(dead_code)]
+            #![allow(non_snake_case)]
+            #![allow(unused_braces)]
+            #![allow(unreachable_patterns)]
+            #![allow(clippy::just_underscores_and_digits)]
+            #![allow(clippy::let_unit_value)]
+            #![allow(clippy::needless_return)]
+            #![allow(clippy::possible_missing_else)]
+            #![allow(clippy::unit_arg)]
+            #![allow(clippy::unused_unit)]
+
+            #inner
         }
     }
 }
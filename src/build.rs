@@ -0,0 +1,257 @@
+use std::{
+    env,
+    error::Error,
+    fmt, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+#[macro_export]
+macro_rules! include_parser {
+    ($path:literal) => {
+        include!(concat!(env!("OUT_DIR"), "/", $path, ".rs"));
+    };
+}
+
+#[derive(Debug)]
+pub struct BuildError {
+    pub failed: Vec<PathBuf>,
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "failed to process grammars:")?;
+
+        for path in self.failed.iter() {
+            write!(f, " {}", path.display())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for BuildError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    in_dir: Option<PathBuf>,
+    out_dir: Option<PathBuf>,
+    emit_rerun_directives: bool,
+}
+
+impl Config {
+    pub fn new() -> Self {
+        Config {
+            emit_rerun_directives: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn set_in_dir(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.in_dir = Some(dir.into());
+        self
+    }
+
+    pub fn set_out_dir(&mut self, dir: impl Into<PathBuf>) -> &mut Self {
+        self.out_dir = Some(dir.into());
+        self
+    }
+
+    pub fn emit_rerun_directives(&mut self, emit: bool) -> &mut Self {
+        self.emit_rerun_directives = emit;
+        self
+    }
+
+    fn in_dir(&self) -> Result<PathBuf, Box<dyn Error>> {
+        match &self.in_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(PathBuf::from(env::var("CARGO_MANIFEST_DIR")?).join("src")),
+        }
+    }
+
+    fn out_dir(&self) -> Result<PathBuf, Box<dyn Error>> {
+        match &self.out_dir {
+            Some(dir) => Ok(dir.clone()),
+            None => Ok(PathBuf::from(env::var("OUT_DIR")?)),
+        }
+    }
+
+    pub fn process(&self) -> Result<(), Box<dyn Error>> {
+        self.process_with(&mut io::stdout())
+    }
+
+    // Cargo reads the rerun directives from stdout; tests pass a buffer.
+    fn process_with(&self, directives: &mut impl Write) -> Result<(), Box<dyn Error>> {
+        let in_dir = self.in_dir()?;
+        let mut grammars = Vec::new();
+        find_grammars(&in_dir, &mut grammars)?;
+        grammars.sort();
+
+        if self.emit_rerun_directives {
+            writeln!(directives, "cargo:rerun-if-changed={}", in_dir.display())?;
+        }
+
+        let mut failed = Vec::new();
+
+        for grammar in grammars {
+            let relative = grammar.strip_prefix(&in_dir)?.with_extension("rs");
+            let out = self.out_dir()?.join(relative);
+
+            if !self.process_file_into(&grammar, &out, directives)? {
+                failed.push(grammar);
+            }
+        }
+
+        if failed.is_empty() {
+            Ok(())
+        } else {
+            Err(Box::new(BuildError { failed }))
+        }
+    }
+
+    pub fn process_file(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        self.process_file_with(path.as_ref(), &mut io::stdout())
+    }
+
+    fn process_file_with(
+        &self,
+        path: &Path,
+        directives: &mut impl Write,
+    ) -> Result<(), Box<dyn Error>> {
+        let file_name = path
+            .with_extension("rs")
+            .file_name()
+            .map(PathBuf::from)
+            .ok_or_else(|| format!("{} is not a file", path.display()))?;
+        let out = self.out_dir()?.join(file_name);
+
+        if self.process_file_into(path, &out, directives)? {
+            Ok(())
+        } else {
+            Err(Box::new(BuildError {
+                failed: vec![path.to_owned()],
+            }))
+        }
+    }
+
+    fn process_file_into(
+        &self,
+        path: &Path,
+        out: &Path,
+        directives: &mut impl Write,
+    ) -> Result<bool, Box<dyn Error>> {
+        if self.emit_rerun_directives {
+            writeln!(directives, "cargo:rerun-if-changed={}", path.display())?;
+        }
+
+        let source = fs::read_to_string(path)?;
+
+        let tokens = match crate::compile(&source) {
+            Ok(tokens) => tokens,
+            Err(diagnostics) => {
+                eprint!(
+                    "{}",
+                    diagnostics.render(&path.display().to_string(), &source)
+                );
+                return Ok(false);
+            }
+        };
+
+        let generated = tokens.to_string();
+
+        if fs::read_to_string(out).ok().as_ref() != Some(&generated) {
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            fs::write(out, generated)?;
+        }
+
+        Ok(true)
+    }
+}
+
+pub fn find_grammars(dir: &Path, grammars: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+
+        if path.is_dir() {
+            find_grammars(&path, grammars)?;
+        } else if path.extension().is_some_and(|ext| ext == "l1l") {
+            grammars.push(path);
+        }
+    }
+
+    Ok(())
+}
+
+pub fn process_root() -> Result<(), Box<dyn Error>> {
+    Config::new().process()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::Config;
+
+    const COUNT: &str = include_str!("../examples/count/count.l1l");
+
+    #[test]
+    fn writes_modules_and_rerun_directives() {
+        let root = env::temp_dir().join(format!("l1l-build-{}", std::process::id()));
+        let (src, out) = (root.join("src"), root.join("out"));
+        fs::create_dir_all(src.join("nested")).unwrap();
+        fs::write(src.join("count.l1l"), COUNT).unwrap();
+        fs::write(src.join("nested/again.l1l"), COUNT).unwrap();
+        fs::write(src.join("notes.txt"), "not a grammar").unwrap();
+
+        let mut directives = Vec::new();
+        let result = Config::new()
+            .set_in_dir(&src)
+            .set_out_dir(&out)
+            .process_with(&mut directives);
+
+        let generated = fs::read_to_string(out.join("count.rs")).unwrap();
+        let nested = fs::read_to_string(out.join("nested/again.rs")).unwrap();
+        let written = fs::read_dir(&out).unwrap().count();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert!(result.is_ok());
+        assert_eq!(generated, crate::compile(COUNT).unwrap().to_string());
+        assert_eq!(nested, generated);
+        assert_eq!(written, 2);
+        assert_eq!(
+            String::from_utf8(directives).unwrap(),
+            format!(
+                "cargo:rerun-if-changed={}\ncargo:rerun-if-changed={}\ncargo:rerun-if-changed={}\n",
+                src.display(),
+                src.join("count.l1l").display(),
+                src.join("nested/again.l1l").display(),
+            )
+        );
+    }
+
+    #[test]
+    fn reports_grammars_with_errors() {
+        let root = env::temp_dir().join(format!("l1l-build-errors-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        let path = root.join("broken.l1l");
+        fs::write(&path, COUNT.replace("\"a\" <cont", "\"b\" <cont")).unwrap();
+
+        let mut directives = Vec::new();
+        let result = Config::new()
+            .set_out_dir(&root)
+            .emit_rerun_directives(false)
+            .process_file_with(&path, &mut directives);
+        let written = root.join("broken.rs").exists();
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            format!("failed to process grammars: {}", path.display())
+        );
+        assert!(!written);
+        assert!(directives.is_empty());
+    }
+}
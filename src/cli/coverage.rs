@@ -0,0 +1,107 @@
+use std::{fs, path::PathBuf};
+
+use generator::{coverage::Coverage, diagnostics::line_col, interpret::Interpreter};
+
+use super::{
+    export::output, read, repl::default_start, Args, Error, MessageFormat, Result, MESSAGE_FORMAT,
+};
+
+fn collect(path: PathBuf, files: &mut Vec<PathBuf>) -> Result<()> {
+    if !path.is_dir() {
+        files.push(path);
+        return Ok(());
+    }
+
+    let io = |e| Error::Io(path.display().to_string(), e);
+    let mut entries = fs::read_dir(&path)
+        .map_err(io)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(io)?;
+    entries.sort();
+
+    for entry in entries {
+        collect(entry, files)?;
+    }
+
+    Ok(())
+}
+
+pub fn coverage(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &["--start", "--format", "-o", "--output", MESSAGE_FORMAT],
+        &[],
+    )?;
+    let format = MessageFormat::from_args(&args)?;
+
+    let Some((path, corpus)) = args.free.split_first() else {
+        return Err(Error::Usage("missing grammar file".to_owned()));
+    };
+
+    if corpus.is_empty() {
+        return Err(Error::Usage("missing corpus files".to_owned()));
+    }
+
+    let report = args.value(&["--format"]).unwrap_or("text");
+
+    if !matches!(report, "text" | "lcov" | "html") {
+        return Err(Error::Usage(format!("unknown coverage format {report}")));
+    }
+
+    let (source, language) = format.load(path)?;
+    format.report(path, &source, &language.diagnostics())?;
+
+    let start = match args.value(&["--start"]) {
+        Some(start) => start.to_owned(),
+        None => default_start(&language)
+            .ok_or_else(|| Error::Usage(format!("{path} defines no nonterminals")))?,
+    };
+
+    let interpreter =
+        Interpreter::new(&language, &start).map_err(|e| Error::Usage(e.to_string()))?;
+
+    let mut files = Vec::new();
+
+    for input in corpus {
+        collect(PathBuf::from(input), &mut files)?;
+    }
+
+    let mut coverage = Coverage::new(&language);
+    let mut failed = 0;
+
+    for file in files.iter() {
+        let name = file.display().to_string();
+        let input = read(&name)?;
+
+        match interpreter.parse(&input) {
+            Ok(tree) => coverage.add(&tree),
+            Err(e) => {
+                failed += 1;
+
+                match e.position() {
+                    Some(position) => {
+                        let (line, col) = line_col(&input, position);
+                        eprintln!("warning: {name}:{line}:{col}: {e}");
+                    }
+                    None => eprintln!("warning: {name}: {e}"),
+                }
+            }
+        }
+    }
+
+    if failed > 0 {
+        eprintln!(
+            "warning: {failed} of {} files did not parse and are not counted",
+            files.len()
+        );
+    }
+
+    let text = match report {
+        "lcov" => coverage.lcov(path, &source),
+        "html" => coverage.html(&source, path),
+        _ => coverage.text(path, &source),
+    };
+
+    output(&args, &text)
+}
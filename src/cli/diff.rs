@@ -0,0 +1,59 @@
+use generator::diff::{self, Section};
+
+use super::{repl::default_start, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+pub fn diff(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--start", MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+
+    let [old_path, new_path] = &args.free[..] else {
+        return Err(Error::Usage(
+            "expected an old and a new grammar file".to_owned(),
+        ));
+    };
+
+    let (_, old) = format.load(old_path)?;
+    let (_, new) = format.load(new_path)?;
+    let mut sections = diff::diff(&old, &new);
+
+    let start = args
+        .value(&["--start"])
+        .map(str::to_owned)
+        .or_else(|| default_start(&new))
+        .or_else(|| default_start(&old));
+
+    if let Some(start) = start {
+        let lines: Vec<_> = [
+            (diff::example(&old, &new, &start), old_path),
+            (diff::example(&new, &old, &start), new_path),
+        ]
+        .into_iter()
+        .filter_map(|(input, path)| Some(format!("only {path} accepts {:?}", input?)))
+        .collect();
+
+        if !lines.is_empty() {
+            sections.push(Section {
+                title: "examples",
+                lines,
+            });
+        }
+    }
+
+    if sections.is_empty() {
+        println!("no differences");
+    }
+
+    for (i, section) in sections.iter().enumerate() {
+        if i > 0 {
+            println!();
+        }
+
+        println!("{}:", section.title);
+
+        for line in section.lines.iter() {
+            println!("    {line}");
+        }
+    }
+
+    Ok(())
+}
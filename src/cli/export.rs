@@ -0,0 +1,81 @@
+use std::path::Path;
+
+use generator::{
+    diagnostics::line_col,
+    export::{
+        diagram, dot,
+        ebnf::{self, Notation},
+        lalrpop, pest, Warning,
+    },
+};
+
+use super::{write, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+pub fn output(args: &Args, contents: &str) -> Result<()> {
+    match args.value(&["-o", "--output"]) {
+        Some(out) => write(Path::new(out), contents),
+        None => {
+            print!("{contents}");
+            Ok(())
+        }
+    }
+}
+
+fn title(path: &str) -> String {
+    Path::new(path).file_stem().map_or_else(
+        || path.to_owned(),
+        |stem| stem.to_string_lossy().into_owned(),
+    )
+}
+
+pub fn diagram(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["-o", "--output", MESSAGE_FORMAT], &[])?;
+    let path = args.single()?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(path)?;
+
+    output(&args, &diagram::html(&language, &title(path)))
+}
+
+pub fn dot(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["-o", "--output", MESSAGE_FORMAT], &[])?;
+    let path = args.single()?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(path)?;
+
+    output(&args, &dot::dot(&language.grammar, &title(path)))
+}
+
+fn warn(path: &str, source: &str, warnings: &[Warning]) {
+    for warning in warnings {
+        match warning.span {
+            Some(span) => {
+                let (line, col) = line_col(source, span.start);
+                eprintln!("warning: {path}:{line}:{col}: {}", warning.message);
+            }
+            None => eprintln!("warning: {path}: {}", warning.message),
+        }
+    }
+}
+
+pub fn export(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--to", "-o", "--output", MESSAGE_FORMAT], &[])?;
+    let path = args.single()?;
+
+    let to = match args.value(&["--to"]) {
+        Some(to @ ("bnf" | "iso-ebnf" | "w3c-ebnf" | "lalrpop" | "pest")) => to,
+        Some(other) => return Err(Error::Usage(format!("unknown export format {other}"))),
+        None => return Err(Error::Usage("missing --to format".to_owned())),
+    };
+
+    let (source, language) = MessageFormat::from_args(&args)?.load(path)?;
+
+    let (exported, warnings) = match to {
+        "bnf" => (ebnf::export(&language, Notation::Bnf), Vec::new()),
+        "iso-ebnf" => (ebnf::export(&language, Notation::IsoEbnf), Vec::new()),
+        "w3c-ebnf" => (ebnf::export(&language, Notation::W3cEbnf), Vec::new()),
+        "lalrpop" => lalrpop::export(&language),
+        _ => pest::export(&language),
+    };
+
+    warn(path, &source, &warnings);
+    output(&args, &exported)
+}
@@ -0,0 +1,66 @@
+use std::{
+    io::{self, Read},
+    path::Path,
+};
+
+use super::{read, write, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+fn format(path: &str, source: &str, format: MessageFormat) -> Result<String> {
+    generator::format(source).map_err(|diagnostics| {
+        format.emit(path, source, &diagnostics);
+        Error::Grammar
+    })
+}
+
+pub fn fmt(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &["--check"])?;
+    let message_format = MessageFormat::from_args(&args)?;
+    let check = args.flag("--check");
+
+    if args.free.is_empty() {
+        return Err(Error::Usage("missing grammar file".to_owned()));
+    }
+
+    let mut unformatted = 0;
+    let mut failed = false;
+
+    for path in args.free.iter() {
+        let source = if path == "-" {
+            let mut source = String::new();
+            io::stdin()
+                .read_to_string(&mut source)
+                .map_err(|e| Error::Io("<stdin>".to_owned(), e))?;
+            source
+        } else {
+            read(path)?
+        };
+
+        let formatted = match format(path, &source, message_format) {
+            Ok(formatted) => formatted,
+            Err(Error::Grammar) => {
+                failed = true;
+                continue;
+            }
+            Err(e) => return Err(e),
+        };
+
+        if check {
+            if formatted != source {
+                println!("{path}");
+                unformatted += 1;
+            }
+        } else if path == "-" {
+            print!("{formatted}");
+        } else if formatted != source {
+            write(Path::new(path), &formatted)?;
+        }
+    }
+
+    if failed {
+        Err(Error::Grammar)
+    } else if unformatted > 0 {
+        Err(Error::Unformatted(unformatted))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::Path;
+
+use super::{write, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+pub fn check(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+
+    if args.free.is_empty() {
+        return Err(Error::Usage("missing grammar file".to_owned()));
+    }
+
+    let mut failed = false;
+
+    for path in args.free.iter() {
+        let checked = format
+            .load(path)
+            .and_then(|(source, language)| format.report(path, &source, &language.diagnostics()));
+
+        match checked {
+            Ok(()) => {}
+            Err(Error::Grammar) => failed = true,
+            Err(e) => return Err(e),
+        }
+    }
+
+    if failed {
+        Err(Error::Grammar)
+    } else {
+        Ok(())
+    }
+}
+
+pub fn compile(path: &str, module: &str, format: MessageFormat) -> Result<String> {
+    let (source, language) = format.load(path)?;
+    format.report(path, &source, &language.diagnostics())?;
+
+    Ok(language.generate_module(module).to_string())
+}
+
+pub fn is_ident(name: &str) -> bool {
+    let mut chars = name.chars();
+
+    chars
+        .next()
+        .is_some_and(|ch| ch == '_' || ch.is_ascii_alphabetic())
+        && chars.all(|ch| ch == '_' || ch.is_ascii_alphanumeric())
+}
+
+pub fn generate(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["-o", "--output", "--module", MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+    let path = args.single()?;
+    let module = args.value(&["--module"]).unwrap_or("parser");
+
+    if !is_ident(module) {
+        return Err(Error::Usage(format!("invalid module name {module}")));
+    }
+
+    let generated = compile(path, module, format)?;
+
+    match args.value(&["-o", "--output"]) {
+        Some(out) => write(Path::new(out), &generated),
+        None => {
+            println!("{generated}");
+            Ok(())
+        }
+    }
+}
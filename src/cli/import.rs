@@ -0,0 +1,48 @@
+use generator::{
+    diagnostics::line_col,
+    import::{self, Import, Issue},
+    notation,
+};
+
+use super::{export::output, read, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+fn warn(path: &str, source: &str, severity: &str, issue: &Issue) {
+    let (line, col) = line_col(source, issue.offset);
+    eprintln!("{severity}: {path}:{line}:{col}: {}", issue.message);
+}
+
+pub fn import(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--from", "-o", "--output", MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+    let path = args.single()?;
+
+    let convert: fn(&str) -> std::result::Result<Import, Issue> = match args.value(&["--from"]) {
+        Some("lalrpop") => import::lalrpop::import,
+        Some("antlr") => import::antlr::import,
+        Some(other) => return Err(Error::Usage(format!("unknown import format {other}"))),
+        None => return Err(Error::Usage("missing --from format".to_owned())),
+    };
+
+    let source = read(path)?;
+    let imported = match convert(&source) {
+        Ok(imported) => imported,
+        Err(issue) => {
+            warn(path, &source, "error", &issue);
+            return Err(Error::Grammar);
+        }
+    };
+
+    for issue in imported.issues.iter() {
+        warn(path, &source, "warning", issue);
+    }
+
+    let converted = notation::format::format(&imported.language, "");
+    output(&args, &converted)?;
+
+    let target = args.value(&["-o", "--output"]).unwrap_or("<stdout>");
+
+    match generator::parse_language(&converted) {
+        Ok(language) => format.report(target, &converted, &language.diagnostics()),
+        Err(diagnostics) => format.report(target, &converted, &diagnostics),
+    }
+}
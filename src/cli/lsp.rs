@@ -0,0 +1,625 @@
+use std::{
+    collections::HashMap,
+    io::{self, BufRead, Write},
+};
+
+use generator::{
+    ast::{Item, Language, MacroDef, Node, NonTerm, Span},
+    diagnostics::{Diagnostics, Severity},
+    lexer::{desc, show, Term},
+};
+use serde_json::{json, Value};
+
+use super::{Args, Error, Result};
+
+const METHOD_NOT_FOUND: i64 = -32601;
+const PARSE_ERROR: i64 = -32700;
+
+fn io_error(e: io::Error) -> Error {
+    Error::Io("<stdio>".to_owned(), e)
+}
+
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+
+    loop {
+        let mut header = String::new();
+
+        if input.read_line(&mut header).map_err(io_error)? == 0 {
+            return Ok(None);
+        }
+
+        let header = header.trim_end();
+
+        if header.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let Some(length) = length else {
+        return Ok(Some(Value::Null));
+    };
+
+    let mut body = vec![0; length];
+    input.read_exact(&mut body).map_err(io_error)?;
+    Ok(Some(serde_json::from_slice(&body).unwrap_or(Value::Null)))
+}
+
+fn write_message(message: &Value) -> Result<()> {
+    let body = message.to_string();
+    let mut stdout = io::stdout().lock();
+
+    write!(stdout, "Content-Length: {}\r\n\r\n{body}", body.len()).map_err(io_error)?;
+    stdout.flush().map_err(io_error)
+}
+
+fn offset(text: &str, position: &Value) -> usize {
+    let line = position["line"].as_u64().unwrap_or(0) as usize;
+    let character = position["character"].as_u64().unwrap_or(0) as usize;
+
+    let Some(start) = text
+        .split_inclusive('\n')
+        .scan(0, |start, line| {
+            let current = *start;
+            *start += line.len();
+            Some(current)
+        })
+        .chain(std::iter::once(text.len()))
+        .nth(line)
+    else {
+        return text.len();
+    };
+
+    let mut units = 0;
+
+    for (i, ch) in text[start..].char_indices() {
+        if units >= character || ch == '\n' {
+            return start + i;
+        }
+
+        units += ch.len_utf16();
+    }
+
+    text.len()
+}
+
+fn position(text: &str, offset: usize) -> Value {
+    let before = text.get(..offset).unwrap_or(text);
+    let line = before.matches('\n').count();
+    let character = before
+        .rfind('\n')
+        .map_or(before, |nl| &before[nl + 1..])
+        .encode_utf16()
+        .count();
+
+    json!({ "line": line, "character": character })
+}
+
+fn range(text: &str, span: Span) -> Value {
+    json!({ "start": position(text, span.start), "end": position(text, span.end) })
+}
+
+enum Symbol<'l> {
+    NonTerm(&'l NonTerm),
+    Macro(&'l NonTerm),
+    Term(&'l Term),
+}
+
+// The innermost macro call around `offset`; its arguments are found as
+// written nodes before this is consulted.
+fn macro_at(items: &[Item], offset: usize) -> Option<&NonTerm> {
+    items.iter().find_map(|item| match item {
+        Item::Node(_) => None,
+        Item::Group { alts, .. } => alts.iter().find_map(|alt| macro_at(alt, offset)),
+        Item::Repeat { item, .. } => macro_at(std::slice::from_ref(item), offset),
+        Item::Macro {
+            name, args, span, ..
+        } => macro_at(args, offset).or_else(|| span.contains(offset).then_some(name)),
+    })
+}
+
+fn symbol_at(language: &Language, offset: usize) -> Option<Symbol<'_>> {
+    let defs = language
+        .grammar
+        .nonterms
+        .iter()
+        .filter(|def| def.helper.is_none())
+        .map(|def| (def, false))
+        .chain(language.grammar.macros.iter().map(|m| (&m.def, true)));
+
+    for (def, is_macro) in defs {
+        if def.name_span.contains(offset) {
+            return Some(if is_macro {
+                Symbol::Macro(&def.name)
+            } else {
+                Symbol::NonTerm(&def.name)
+            });
+        }
+
+        for node in def.rules.iter().flat_map(|rule| rule.written()) {
+            if node.span().contains(offset) {
+                return Some(match node {
+                    Node::NonTerm { node, .. } => Symbol::NonTerm(node),
+                    Node::Term(term, _, _) => Symbol::Term(term),
+                });
+            }
+        }
+
+        let calls = def.rules.iter().filter_map(|rule| rule.items.as_deref());
+
+        for items in calls {
+            if let Some(name) = macro_at(items, offset) {
+                return Some(Symbol::Macro(name));
+            }
+        }
+    }
+
+    for group in language.tests.iter().flat_map(|tests| tests.groups.iter()) {
+        if group.name_span.contains(offset) {
+            return Some(Symbol::NonTerm(&group.nonterm));
+        }
+    }
+
+    language
+        .lexer
+        .mapping
+        .iter()
+        .find(|(_, _, span)| span.contains(offset))
+        .map(|(term, _, _)| Symbol::Term(term))
+}
+
+fn signature(m: &MacroDef) -> String {
+    format!("{}<{}>", m.def.name.0, m.params.join(", "))
+}
+
+struct Document {
+    text: String,
+    language: Option<Language>,
+    last_parsed: Option<Language>,
+}
+
+impl Document {
+    fn definition(&self, offset: usize) -> Option<Span> {
+        let language = self.language.as_ref()?;
+
+        match symbol_at(language, offset)? {
+            Symbol::NonTerm(name) => language
+                .grammar
+                .nonterms
+                .iter()
+                .find(|def| &def.name == name)
+                .map(|def| def.name_span),
+            Symbol::Macro(name) => language
+                .grammar
+                .macros
+                .iter()
+                .find(|m| &m.def.name == name)
+                .map(|m| m.def.name_span),
+            Symbol::Term(term) => language
+                .lexer
+                .mapping
+                .iter()
+                .find(|(t, _, _)| t == term)
+                .map(|(_, _, span)| *span),
+        }
+    }
+
+    fn hover(&self, offset: usize) -> Option<String> {
+        let language = self.language.as_ref()?;
+
+        match symbol_at(language, offset)? {
+            Symbol::NonTerm(name) => {
+                let fst = language.grammar.build_first();
+                let flw = language.grammar.build_follow(&fst);
+
+                let set = |sets: &HashMap<NonTerm, _>, none| {
+                    let mut terms: Vec<_> = sets.get(name).into_iter().flatten().collect();
+                    terms.sort();
+                    terms
+                        .into_iter()
+                        .map(|term| show(term, none))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                };
+
+                Some(format!(
+                    "**{}**\n\nFIRST: `{}`\n\nFOLLOW: `{}`",
+                    name.0,
+                    set(&fst, "ε"),
+                    set(&flw, "$")
+                ))
+            }
+            Symbol::Macro(name) => {
+                let m = language
+                    .grammar
+                    .macros
+                    .iter()
+                    .find(|m| &m.def.name == name)?;
+
+                Some(format!(
+                    "**{}**\n\n`!{{ {} }}!`",
+                    signature(m),
+                    m.def.ret_ty.trim()
+                ))
+            }
+            Symbol::Term(term) => {
+                let (_, tok, _) = language.lexer.mapping.iter().find(|(t, _, _)| t == term)?;
+
+                Some(format!("`\"{}\" = {}`", term.0, desc(tok)))
+            }
+        }
+    }
+
+    fn completion(&self, offset: usize) -> Vec<Value> {
+        let Some(language) = self.language.as_ref().or(self.last_parsed.as_ref()) else {
+            return Vec::new();
+        };
+
+        let quoted = self.text[..offset.min(self.text.len())].ends_with('"');
+
+        let nonterms = language
+            .grammar
+            .nonterms
+            .iter()
+            .filter(|def| def.helper.is_none())
+            .map(|def| {
+                json!({
+                    "label": def.name.0,
+                    "kind": 7,
+                    "detail": format!("!{{ {} }}!", def.ret_ty.trim()),
+                })
+            });
+
+        let macros = language.grammar.macros.iter().map(|m| {
+            json!({
+                "label": m.def.name.0,
+                "kind": 3,
+                "detail": signature(m),
+            })
+        });
+
+        let terms = language.lexer.mapping.iter().map(|(term, _, _)| {
+            let insert = if quoted {
+                term.0.clone()
+            } else {
+                format!("\"{}\"", term.0)
+            };
+
+            json!({
+                "label": format!("\"{}\"", term.0),
+                "kind": 21,
+                "filterText": format!("\"{}\"", term.0),
+                "insertText": insert,
+            })
+        });
+
+        nonterms.chain(macros).chain(terms).collect()
+    }
+}
+
+fn lsp_diagnostics(uri: &str, text: &str, diagnostics: &Diagnostics) -> Vec<Value> {
+    diagnostics
+        .iter()
+        .map(|diagnostic| {
+            let spans = diagnostic.spans();
+            let primary = spans.first().copied().unwrap_or_default();
+            let related: Vec<_> = spans
+                .iter()
+                .skip(1)
+                .map(|&span| {
+                    json!({
+                        "location": { "uri": uri, "range": range(text, span) },
+                        "message": "conflicting alternative",
+                    })
+                })
+                .collect();
+            let severity = match diagnostic.severity() {
+                Severity::Error => 1,
+                Severity::Warning => 2,
+            };
+
+            json!({
+                "range": range(text, primary),
+                "severity": severity,
+                "code": diagnostic.code(),
+                "source": "l1l",
+                "message": diagnostic.to_string(),
+                "relatedInformation": related,
+            })
+        })
+        .collect()
+}
+
+#[derive(Default)]
+struct Server {
+    documents: HashMap<String, Document>,
+}
+
+impl Server {
+    fn update(&mut self, uri: &str, text: String) -> Result<()> {
+        let (language, diagnostics) = match generator::parse_language(&text) {
+            Ok(language) => {
+                let diagnostics = language.diagnostics();
+                (Some(language), diagnostics)
+            }
+            Err(diagnostics) => (None, diagnostics),
+        };
+
+        let last_parsed = match (&language, self.documents.remove(uri)) {
+            (Some(language), _) => Some(language.clone()),
+            (None, Some(old)) => old.last_parsed,
+            (None, None) => None,
+        };
+
+        write_message(&json!({
+            "jsonrpc": "2.0",
+            "method": "textDocument/publishDiagnostics",
+            "params": { "uri": uri, "diagnostics": lsp_diagnostics(uri, &text, &diagnostics) },
+        }))?;
+
+        self.documents.insert(
+            uri.to_owned(),
+            Document {
+                text,
+                language,
+                last_parsed,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn document<'s>(&'s self, params: &'s Value) -> Option<(&'s str, &'s Document, usize)> {
+        let uri = params["textDocument"]["uri"].as_str()?;
+        let document = self.documents.get(uri)?;
+        let offset = offset(&document.text, &params["position"]);
+
+        Some((uri, document, offset))
+    }
+
+    fn request(&mut self, method: &str, params: &Value) -> std::result::Result<Value, i64> {
+        match method {
+            "initialize" => Ok(json!({
+                "capabilities": {
+                    "textDocumentSync": 1,
+                    "definitionProvider": true,
+                    "hoverProvider": true,
+                    "completionProvider": { "triggerCharacters": ["\"", "<", ":"] },
+                },
+                "serverInfo": { "name": "l1l" },
+            })),
+            "shutdown" => Ok(Value::Null),
+            "textDocument/definition" => Ok(self
+                .document(params)
+                .and_then(|(uri, document, offset)| {
+                    let span = document.definition(offset)?;
+                    Some(json!({ "uri": uri, "range": range(&document.text, span) }))
+                })
+                .unwrap_or(Value::Null)),
+            "textDocument/hover" => Ok(self
+                .document(params)
+                .and_then(|(_, document, offset)| document.hover(offset))
+                .map(|value| json!({ "contents": { "kind": "markdown", "value": value } }))
+                .unwrap_or(Value::Null)),
+            "textDocument/completion" => Ok(self
+                .document(params)
+                .map(|(_, document, offset)| Value::from(document.completion(offset)))
+                .unwrap_or(Value::Null)),
+            _ => Err(METHOD_NOT_FOUND),
+        }
+    }
+
+    fn notification(&mut self, method: &str, params: &Value) -> Result<()> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default();
+
+        match method {
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                self.update(uri, text.to_owned())
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+
+                match changes.and_then(|changes| changes.last()?["text"].as_str()) {
+                    Some(text) => self.update(uri, text.to_owned()),
+                    None => Ok(()),
+                }
+            }
+            "textDocument/didClose" => {
+                self.documents.remove(uri);
+
+                write_message(&json!({
+                    "jsonrpc": "2.0",
+                    "method": "textDocument/publishDiagnostics",
+                    "params": { "uri": uri, "diagnostics": [] },
+                }))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+pub fn lsp(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[], &["--stdio"])?;
+
+    if !args.free.is_empty() {
+        return Err(Error::Usage("lsp takes no grammar files".to_owned()));
+    }
+
+    let mut server = Server::default();
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+
+        if method == "exit" {
+            break;
+        }
+
+        match message.get("id") {
+            Some(id) if message.get("method").is_some() => {
+                let response = match server.request(method, params) {
+                    Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                    Err(code) => json!({
+                        "jsonrpc": "2.0",
+                        "id": id,
+                        "error": { "code": code, "message": format!("unsupported method {method}") },
+                    }),
+                };
+
+                write_message(&response)?;
+            }
+            Some(_) => {}
+            None if message.is_null() => write_message(&json!({
+                "jsonrpc": "2.0",
+                "id": null,
+                "error": { "code": PARSE_ERROR, "message": "invalid message" },
+            }))?,
+            None => server.notification(method, params)?,
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use serde_json::json;
+
+    use super::{offset, position, read_message, Document};
+
+    const SOURCE: &str = r#"preamble = !{ }!
+
+tokens = {
+    "n" = regex "[0-9]+",
+    "," = token ",",
+}
+
+rules = {
+    item: !{ () }! = {
+        "n" => !{ () }!,
+    }
+
+    pub list: !{ () }! = {
+        item ("," item)* => !{ () }!,
+    }
+}
+"#;
+
+    fn document() -> Document {
+        Document {
+            text: SOURCE.to_owned(),
+            language: Some(generator::parse_language(SOURCE).unwrap()),
+            last_parsed: None,
+        }
+    }
+
+    #[test]
+    fn positions_count_utf16_units() {
+        let text = "a\né𝄞b\n";
+
+        assert_eq!(position(text, 4), json!({ "line": 1, "character": 1 }));
+        assert_eq!(position(text, 8), json!({ "line": 1, "character": 3 }));
+        assert_eq!(offset(text, &json!({ "line": 1, "character": 3 })), 8);
+        assert_eq!(
+            offset(text, &json!({ "line": 5, "character": 0 })),
+            text.len()
+        );
+    }
+
+    #[test]
+    fn reads_framed_messages() {
+        let body = r#"{"method":"exit"}"#;
+        let mut input = Cursor::new(format!("Content-Length: {}\r\n\r\n{body}", body.len()));
+
+        assert_eq!(
+            read_message(&mut input).unwrap(),
+            Some(json!({ "method": "exit" }))
+        );
+        assert_eq!(read_message(&mut input).unwrap(), None);
+    }
+
+    #[test]
+    fn finds_symbols_inside_repetitions() {
+        let document = document();
+        let inner = SOURCE.rfind("item)").unwrap();
+        let definition = document.definition(inner).unwrap();
+
+        assert_eq!(&SOURCE[definition.start..definition.end], "item");
+        assert_eq!(definition.start, SOURCE.find("item:").unwrap());
+        assert_eq!(
+            document.hover(inner).unwrap(),
+            "**item**\n\nFIRST: `\"n\"`\n\nFOLLOW: `$ \",\"`"
+        );
+        assert_eq!(
+            document.hover(SOURCE.rfind("\",\"").unwrap()).unwrap(),
+            "`\",\" = token \",\"`"
+        );
+    }
+
+    #[test]
+    fn completes_written_nonterminals() {
+        let labels: Vec<_> = document()
+            .completion(0)
+            .into_iter()
+            .map(|item| item["label"].as_str().unwrap().to_owned())
+            .collect();
+
+        assert_eq!(labels, ["item", "list", "\"n\"", "\",\""]);
+    }
+
+    #[test]
+    fn resolves_macro_calls() {
+        let source = r#"preamble = !{ }!
+
+tokens = {
+    "n" = regex "[0-9]+",
+    "," = token ",",
+}
+
+rules = {
+    Comma<T>: !{ () }! = {
+        T ("," T)* => !{ () }!,
+    }
+
+    item: !{ () }! = {
+        "n" => !{ () }!,
+    }
+
+    pub list: !{ () }! = {
+        Comma<item> => !{ () }!,
+    }
+}
+"#;
+        let document = Document {
+            text: source.to_owned(),
+            language: Some(generator::parse_language(source).unwrap()),
+            last_parsed: None,
+        };
+        let call = source.find("Comma<item>").unwrap();
+
+        let definition = document.definition(call).unwrap();
+        assert_eq!(definition.start, source.find("Comma<T>").unwrap());
+        assert_eq!(document.hover(call).unwrap(), "**Comma<T>**\n\n`!{ () }!`");
+
+        let argument = document.definition(call + "Comma<".len()).unwrap();
+        assert_eq!(argument.start, source.find("item:").unwrap());
+
+        let labels: Vec<_> = document
+            .completion(0)
+            .into_iter()
+            .map(|item| item["label"].as_str().unwrap().to_owned())
+            .collect();
+        assert_eq!(labels, ["item", "list", "Comma", "\"n\"", "\",\""]);
+    }
+}
@@ -0,0 +1,309 @@
+use std::{fmt, fs, io, path::Path, process::ExitCode};
+
+use generator::{ast::Language, diagnostics::Diagnostics};
+
+mod coverage;
+mod diff;
+mod export;
+mod format;
+mod generate;
+mod import;
+mod lsp;
+mod repl;
+mod sample;
+mod sets;
+mod stats;
+mod test;
+mod watch;
+
+const USAGE: &str = "\
+Usage: l1l <command> [options] <grammar.l1l>...
+
+Options:
+    --message-format=human|json          Diagnostic output format
+
+Commands:
+    check <files>...                     Validate grammars
+    test <files>...                      Run the accept/reject cases in tests sections
+    generate <file> [-o out.rs] [--module name]
+                                         Generate a parser module
+    diagram <file> [-o out.html]         Render railroad diagrams as HTML
+    dot <file> [-o out.dot]              Export the nonterminal graph for Graphviz
+    export <file> --to bnf|iso-ebnf|w3c-ebnf|lalrpop|pest [-o out]
+                                         Print the grammar in another notation
+    import <file> --from lalrpop|antlr [-o out.l1l]
+                                         Convert a grammar from another generator
+    fmt [--check] <files>...             Rewrite grammars in the canonical layout
+    diff <old> <new> [--start nt]        Compare two versions of a grammar
+    first <file>                         Print FIRST sets
+    follow <file>                        Print FOLLOW sets
+    table <file>                         Print the LL(1) predict table
+    stats <file> [--format text|json] [-o out]
+                                         Print size and complexity figures
+    lsp                                  Run a language server on stdio
+    repl <file> [--start nt]             Parse input lines interactively
+    sample <file> [--start nt] [--count n] [--max-depth n] [--seed n] [-o out]
+                                         Print random sentences of the grammar
+    cover <file> --out-dir dir [--start nt] [--seed n]
+                                         Write inputs that use every alternative
+    coverage <file> <inputs|dirs>... [--start nt] [--format text|lcov|html] [-o out]
+                                         Report the alternatives and tokens a corpus uses
+    watch <dir|file> [--out-dir dir] [--module name] [--interval ms]
+                                         Regenerate parsers when grammars change
+";
+
+#[derive(Debug)]
+pub enum Error {
+    Usage(String),
+    Io(String, io::Error),
+    Grammar,
+    Unformatted(usize),
+    TestsFailed(usize),
+}
+
+impl Error {
+    fn exit_code(&self) -> u8 {
+        match self {
+            Error::Grammar | Error::Unformatted(_) | Error::TestsFailed(_) => 1,
+            Error::Usage(_) => 2,
+            Error::Io(_, _) => 3,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Usage(msg) => write!(f, "{msg}\n\n{USAGE}"),
+            Error::Io(path, e) => write!(f, "{path}: {e}"),
+            Error::Grammar => write!(f, "grammar has errors"),
+            Error::Unformatted(1) => write!(f, "1 file needs formatting"),
+            Error::Unformatted(n) => write!(f, "{n} files need formatting"),
+            Error::TestsFailed(1) => write!(f, "1 test failed"),
+            Error::TestsFailed(n) => write!(f, "{n} tests failed"),
+        }
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub struct Args {
+    pub free: Vec<String>,
+    options: Vec<(String, Option<String>)>,
+}
+
+impl Args {
+    pub fn parse(args: &[String], values: &[&str], flags: &[&str]) -> Result<Args> {
+        let mut free = Vec::new();
+        let mut options = Vec::new();
+        let mut iter = args.iter();
+
+        while let Some(arg) = iter.next() {
+            if !arg.starts_with('-') || arg == "-" {
+                free.push(arg.clone());
+                continue;
+            }
+
+            let (name, inline) = match arg.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_owned())),
+                None => (&arg[..], None),
+            };
+
+            if values.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => iter
+                        .next()
+                        .cloned()
+                        .ok_or_else(|| Error::Usage(format!("option {name} requires a value")))?,
+                };
+                options.push((name.to_owned(), Some(value)));
+            } else if flags.contains(&name) && inline.is_none() {
+                options.push((name.to_owned(), None));
+            } else {
+                return Err(Error::Usage(format!("unknown option {arg}")));
+            }
+        }
+
+        Ok(Args { free, options })
+    }
+
+    pub fn value(&self, names: &[&str]) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(name, _)| names.contains(&&name[..]))
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(option, _)| option == name)
+    }
+
+    pub fn single(&self) -> Result<&str> {
+        match &self.free[..] {
+            [one] => Ok(one),
+            [] => Err(Error::Usage("missing grammar file".to_owned())),
+            _ => Err(Error::Usage("expected exactly one grammar file".to_owned())),
+        }
+    }
+}
+
+pub fn read(path: &str) -> Result<String> {
+    fs::read_to_string(path).map_err(|e| Error::Io(path.to_owned(), e))
+}
+
+pub fn write(path: &Path, contents: &str) -> Result<()> {
+    fs::write(path, contents).map_err(|e| Error::Io(path.display().to_string(), e))
+}
+
+pub const MESSAGE_FORMAT: &str = "--message-format";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MessageFormat {
+    Human,
+    Json,
+}
+
+impl MessageFormat {
+    pub fn from_args(args: &Args) -> Result<MessageFormat> {
+        match args.value(&[MESSAGE_FORMAT]) {
+            None | Some("human") => Ok(MessageFormat::Human),
+            Some("json") => Ok(MessageFormat::Json),
+            Some(other) => Err(Error::Usage(format!("unknown message format {other}"))),
+        }
+    }
+
+    pub fn emit(self, path: &str, source: &str, diagnostics: &Diagnostics) {
+        match self {
+            MessageFormat::Human => eprint!("{}", diagnostics.render(path, source)),
+            MessageFormat::Json => {
+                for diagnostic in diagnostics.iter() {
+                    println!("{}", diagnostic.to_json(path, source));
+                }
+            }
+        }
+    }
+
+    pub fn report(self, path: &str, source: &str, diagnostics: &Diagnostics) -> Result<()> {
+        self.emit(path, source, diagnostics);
+
+        if diagnostics.has_errors() {
+            Err(Error::Grammar)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn load(self, path: &str) -> Result<(String, Language)> {
+        let source = read(path)?;
+
+        match generator::parse_language(&source) {
+            Ok(language) => Ok((source, language)),
+            Err(diagnostics) => {
+                self.emit(path, &source, &diagnostics);
+                Err(Error::Grammar)
+            }
+        }
+    }
+}
+
+// Global options may come before the command; they are moved after it so
+// that the command parses them along with its own.
+fn command_first(args: &[String]) -> Result<Vec<String>> {
+    let mut global = Vec::new();
+    let mut args = args;
+
+    while let Some((arg, rest)) = args.split_first() {
+        if arg == MESSAGE_FORMAT {
+            let (value, rest) = rest
+                .split_first()
+                .ok_or_else(|| Error::Usage(format!("option {MESSAGE_FORMAT} requires a value")))?;
+            global.extend([arg.clone(), value.clone()]);
+            args = rest;
+        } else if arg.starts_with(&format!("{MESSAGE_FORMAT}=")) {
+            global.push(arg.clone());
+            args = rest;
+        } else {
+            break;
+        }
+    }
+
+    Ok(match args.split_first() {
+        Some((command, rest)) => [std::slice::from_ref(command), &global[..], rest].concat(),
+        None => global,
+    })
+}
+
+fn dispatch(args: &[String]) -> Result<()> {
+    let args = command_first(args)?;
+    let Some((command, rest)) = args.split_first() else {
+        return Err(Error::Usage("missing command".to_owned()));
+    };
+
+    match &command[..] {
+        "check" => generate::check(rest),
+        "test" => test::test(rest),
+        "generate" => generate::generate(rest),
+        "diagram" => export::diagram(rest),
+        "dot" => export::dot(rest),
+        "export" => export::export(rest),
+        "import" => import::import(rest),
+        "fmt" => format::fmt(rest),
+        "diff" => diff::diff(rest),
+        "first" => sets::first(rest),
+        "follow" => sets::follow(rest),
+        "table" => sets::table(rest),
+        "stats" => stats::stats(rest),
+        "lsp" => lsp::lsp(rest),
+        "repl" => repl::repl(rest),
+        "sample" => sample::sample(rest),
+        "cover" => sample::cover(rest),
+        "coverage" => coverage::coverage(rest),
+        "watch" => watch::watch(rest),
+        "help" | "-h" | "--help" => {
+            print!("{USAGE}");
+            Ok(())
+        }
+        _ => Err(Error::Usage(format!("unknown command {command}"))),
+    }
+}
+
+pub fn run(args: &[String]) -> ExitCode {
+    match dispatch(args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            if !matches!(e, Error::Grammar) {
+                eprintln!("error: {e}");
+            }
+
+            ExitCode::from(e.exit_code())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::command_first;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn global_options_go_after_the_command() {
+        assert_eq!(
+            command_first(&args(&["--message-format=json", "check", "a.l1l"])).unwrap(),
+            args(&["check", "--message-format=json", "a.l1l"])
+        );
+        assert_eq!(
+            command_first(&args(&["--message-format", "json", "check", "a.l1l"])).unwrap(),
+            args(&["check", "--message-format", "json", "a.l1l"])
+        );
+        assert_eq!(
+            command_first(&args(&["check", "--message-format=json", "a.l1l"])).unwrap(),
+            args(&["check", "--message-format=json", "a.l1l"])
+        );
+        assert!(command_first(&args(&["--message-format"])).is_err());
+    }
+}
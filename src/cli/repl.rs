@@ -0,0 +1,259 @@
+use std::io::{self, BufRead, IsTerminal, Write};
+
+use generator::{
+    ast::{Language, NonTerm},
+    interpret::Interpreter,
+    lexer::show,
+};
+
+use super::{Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+const HELP: &str = "\
+<input>              Parse the input from the start nonterminal
+:start [nt]          Show or change the start nonterminal
+:tokens <input>      Show how the input is tokenized
+:first <nt>          Print the FIRST set of a nonterminal
+:follow <nt>         Print the FOLLOW set of a nonterminal
+:reload              Reload the grammar file
+:help                Show this message
+:quit                Exit
+";
+
+struct Repl {
+    path: String,
+    format: MessageFormat,
+    language: Language,
+    start: String,
+}
+
+pub fn default_start(language: &Language) -> Option<String> {
+    let nonterms = &language.grammar.nonterms;
+
+    nonterms
+        .iter()
+        .find(|def| def.is_pub)
+        .or_else(|| nonterms.first())
+        .map(|def| def.name.0.clone())
+}
+
+fn defines(language: &Language, name: &str) -> bool {
+    language
+        .grammar
+        .nonterms
+        .iter()
+        .any(|def| def.name.0 == name)
+}
+
+fn caret(input: &str, position: usize) -> String {
+    let column = input[..position.min(input.len())].chars().count();
+    format!("{}^", " ".repeat(column))
+}
+
+impl Repl {
+    fn load(path: &str, format: MessageFormat) -> Result<Language> {
+        let (source, language) = format.load(path)?;
+        format.report(path, &source, &language.diagnostics())?;
+        Ok(language)
+    }
+
+    fn print_set(&self, name: &str, follow: bool) {
+        if !defines(&self.language, name) {
+            println!("unknown nonterminal {name}");
+            return;
+        }
+
+        let fst = self.language.grammar.build_first();
+        let (sets, none) = if follow {
+            (self.language.grammar.build_follow(&fst), "$")
+        } else {
+            (fst, "ε")
+        };
+
+        let mut terms: Vec<_> = sets
+            .get(&NonTerm(name.to_owned()))
+            .into_iter()
+            .flatten()
+            .collect();
+        terms.sort();
+
+        let terms: Vec<_> = terms.into_iter().map(|term| show(term, none)).collect();
+        println!("{name}: {}", terms.join(" "));
+    }
+
+    fn tokens(&self, input: &str) {
+        let interpreter = match Interpreter::new(&self.language, &self.start) {
+            Ok(interpreter) => interpreter,
+            Err(e) => {
+                println!("error: {e}");
+                return;
+            }
+        };
+
+        match interpreter.scanner().tokenize(input) {
+            Ok(lexemes) => {
+                for lexeme in lexemes {
+                    println!(
+                        "{}..{} {} {:?}",
+                        lexeme.span.start,
+                        lexeme.span.end,
+                        show(&Some(lexeme.term), "$"),
+                        lexeme.text
+                    );
+                }
+            }
+            Err(position) => {
+                println!("{input}\n{}", caret(input, position));
+                println!("error: no token matches at {position}");
+            }
+        }
+    }
+
+    fn parse(&self, input: &str) {
+        match self.language.interpret(&self.start, input) {
+            Ok(tree) => print!("{tree}"),
+            Err(e) => {
+                if let Some(position) = e.position() {
+                    println!("{input}\n{}", caret(input, position));
+                    println!("error at {position}: {e}");
+                } else {
+                    println!("error: {e}");
+                }
+            }
+        }
+    }
+
+    fn command(&mut self, line: &str) -> Result<bool> {
+        let (command, arg) = match line.split_once(char::is_whitespace) {
+            Some((command, arg)) => (command, arg.trim()),
+            None => (line, ""),
+        };
+
+        match (command, arg) {
+            (":quit" | ":q", _) => return Ok(false),
+            (":help" | ":h", _) => print!("{HELP}"),
+            (":start", "") => println!("{}", self.start),
+            (":start", name) if defines(&self.language, name) => self.start = name.to_owned(),
+            (":start", name) => println!("unknown nonterminal {name}"),
+            (":tokens", input) => self.tokens(input),
+            (":first", name) => self.print_set(name, false),
+            (":follow", name) => self.print_set(name, true),
+            (":reload", _) => match Repl::load(&self.path, self.format) {
+                Ok(language) => {
+                    if !defines(&language, &self.start) {
+                        self.start = default_start(&language).unwrap_or_default();
+                    }
+                    self.language = language;
+                }
+                Err(Error::Grammar) => println!("grammar has errors, keeping the previous one"),
+                Err(e) => return Err(e),
+            },
+            _ => println!("unknown command {command}, try :help"),
+        }
+
+        Ok(true)
+    }
+}
+
+pub fn repl(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--start", MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+    let path = args.single()?;
+    let language = Repl::load(path, format)?;
+
+    let start = match args.value(&["--start"]) {
+        Some(start) => start.to_owned(),
+        None => default_start(&language)
+            .ok_or_else(|| Error::Usage(format!("{path} defines no nonterminals")))?,
+    };
+
+    let mut repl = Repl {
+        path: path.to_owned(),
+        format,
+        language,
+        start,
+    };
+
+    if !defines(&repl.language, &repl.start) {
+        return Err(Error::Usage(format!(
+            "unknown start nonterminal {}",
+            repl.start
+        )));
+    }
+
+    let stdin = io::stdin();
+    let interactive = stdin.is_terminal();
+    let mut lines = stdin.lock().lines();
+
+    loop {
+        if interactive {
+            print!("{}> ", repl.start);
+            io::stdout().flush().ok();
+        }
+
+        let Some(line) = lines.next() else {
+            break;
+        };
+        let line = line.map_err(|e| Error::Io("<stdin>".to_owned(), e))?;
+
+        if line.starts_with(':') {
+            if !repl.command(line.trim())? {
+                break;
+            }
+        } else if !line.trim().is_empty() {
+            repl.parse(&line);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::{Error, MessageFormat, Repl};
+
+    const SUM: &str = r#"
+preamble = !{ }!
+
+tokens = {
+    "+" = token "+",
+    "n" = regex "[0-9]+",
+}
+
+rules = {
+    pub sum: !{ () }! = {
+        "n" ("+" "n")* => !{ () }!,
+    }
+}
+"#;
+
+    #[test]
+    fn keeps_the_previous_grammar_on_errors() {
+        let path = env::temp_dir().join(format!("l1l-repl-{}.l1l", std::process::id()));
+        let path = path.to_str().unwrap();
+        fs::write(path, SUM).unwrap();
+
+        let mut repl = Repl {
+            path: path.to_owned(),
+            format: MessageFormat::Human,
+            language: Repl::load(path, MessageFormat::Human).unwrap(),
+            start: "sum".to_owned(),
+        };
+
+        fs::write(
+            path,
+            SUM.replace(r#""n" ("+" "n")*"#, r#"sum "+" "n" => !{ () }!, "n""#),
+        )
+        .unwrap();
+        assert!(matches!(
+            Repl::load(path, MessageFormat::Human),
+            Err(Error::Grammar)
+        ));
+        assert!(repl.command(":reload").unwrap());
+        fs::remove_file(path).unwrap();
+
+        assert_eq!(repl.start, "sum");
+        assert!(repl.language.interpret("sum", "1 + 2").is_ok());
+    }
+}
@@ -0,0 +1,231 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use generator::{
+    ast::{Language, NonTerm, Span},
+    diagnostics::line_col,
+    interpret::Interpreter,
+    sample::Sampler,
+};
+
+use super::{
+    export::output, repl::default_start, write, Args, Error, MessageFormat, Result, MESSAGE_FORMAT,
+};
+
+const ATTEMPTS: usize = 20;
+
+fn number<T: std::str::FromStr>(args: &Args, name: &str, default: T) -> Result<T> {
+    match args.value(&[name]) {
+        Some(value) => value
+            .parse()
+            .map_err(|_| Error::Usage(format!("invalid {name} {value}"))),
+        None => Ok(default),
+    }
+}
+
+fn usage(e: impl std::fmt::Display) -> Error {
+    Error::Usage(e.to_string())
+}
+
+fn load(args: &Args) -> Result<(String, Language, String)> {
+    let format = MessageFormat::from_args(args)?;
+    let path = args.single()?;
+    let (source, language) = format.load(path)?;
+    format.report(path, &source, &language.diagnostics())?;
+
+    let start = match args.value(&["--start"]) {
+        Some(start) => start.to_owned(),
+        None => default_start(&language)
+            .ok_or_else(|| Error::Usage(format!("{path} defines no nonterminals")))?,
+    };
+
+    Ok((source, language, start))
+}
+
+fn seed(args: &Args) -> Result<u64> {
+    number(
+        args,
+        "--seed",
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64),
+    )
+}
+
+// Helper alternatives are named after the written rule and item they come
+// from; a helper with a single alternative makes no choice and is left out.
+fn alternative(language: &Language, nonterm: &NonTerm, r: usize) -> Option<(String, Span)> {
+    let grammar = &language.grammar;
+    let def = grammar
+        .nonterms
+        .iter()
+        .find(|def| &def.name == nonterm)
+        .expect("covered rules belong to the grammar");
+    let (owner, o) = grammar.owning_rule(def, r);
+
+    if owner.name != def.name && def.rules.len() < 2 {
+        return None;
+    }
+
+    Some((grammar.label(def, r), owner.rules[o].span))
+}
+
+pub fn sample(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &[
+            "--start",
+            "--count",
+            "--max-depth",
+            "--seed",
+            "-o",
+            "--output",
+            MESSAGE_FORMAT,
+        ],
+        &[],
+    )?;
+    let (_, language, start) = load(&args)?;
+
+    let count = number(&args, "--count", 10)?;
+    let max_depth = number(&args, "--max-depth", 12)?;
+
+    let interpreter = Interpreter::new(&language, &start).map_err(usage)?;
+    let mut sampler = Sampler::new(&language, max_depth, seed(&args)?).map_err(usage)?;
+    let mut out = String::new();
+
+    for i in 0..count {
+        let mut rejected = None;
+
+        for _ in 0..ATTEMPTS {
+            let sentence = sampler.sentence(&start).map_err(usage)?;
+
+            match interpreter.parse(&sentence) {
+                Ok(_) => {
+                    out.push_str(&sentence);
+                    out.push('\n');
+                    rejected = None;
+                    break;
+                }
+                Err(e) => rejected = Some((sentence, e)),
+            }
+        }
+
+        if let Some((sentence, e)) = rejected {
+            eprintln!(
+                "warning: sample {} skipped, {sentence:?} does not parse back: {e}",
+                i + 1
+            );
+        }
+    }
+
+    output(&args, &out)
+}
+
+pub fn cover(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &["--start", "--out-dir", "--seed", MESSAGE_FORMAT],
+        &[],
+    )?;
+    let (source, language, start) = load(&args)?;
+    let path = args.single()?;
+    let out_dir = Path::new(
+        args.value(&["--out-dir"])
+            .ok_or_else(|| Error::Usage("missing --out-dir".to_owned()))?,
+    );
+
+    let interpreter = Interpreter::new(&language, &start).map_err(usage)?;
+    let mut sampler = Sampler::new(&language, 0, seed(&args)?).map_err(usage)?;
+    let coverage = sampler.cover(&start).map_err(usage)?;
+
+    fs::create_dir_all(out_dir).map_err(|e| Error::Io(out_dir.display().to_string(), e))?;
+
+    let width = coverage.cases.len().to_string().len().max(3);
+    let mut labels = String::new();
+
+    for (i, case) in coverage.cases.iter().enumerate() {
+        let name = format!("{:0width$}.txt", i + 1);
+        let mut sentence = sampler.render(&case.terms);
+
+        for _ in 1..ATTEMPTS {
+            if interpreter.parse(&sentence).is_ok() {
+                break;
+            }
+
+            sentence = sampler.render(&case.terms);
+        }
+
+        if let Err(e) = interpreter.parse(&sentence) {
+            eprintln!("warning: {name}: {sentence:?} does not parse back: {e}");
+        }
+
+        write(&out_dir.join(&name), &format!("{sentence}\n"))?;
+
+        let _ = writeln!(labels, "{name}");
+
+        for (nonterm, r) in case.rules.iter() {
+            if let Some((label, _)) = alternative(&language, nonterm, *r) {
+                let _ = writeln!(labels, "    {label}");
+            }
+        }
+    }
+
+    write(&out_dir.join("labels.txt"), &labels)?;
+
+    for (nonterm, r) in coverage.uncovered.iter() {
+        if let Some((label, span)) = alternative(&language, nonterm, *r) {
+            let (line, col) = line_col(&source, span.start);
+            eprintln!("warning: {path}:{line}:{col}: {label} is not covered");
+        }
+    }
+
+    for term in coverage.unused.iter() {
+        let span = language
+            .lexer
+            .mapping
+            .iter()
+            .find(|(t, _, _)| t == *term)
+            .map(|(_, _, span)| span.start)
+            .unwrap_or(0);
+        let (line, col) = line_col(&source, span);
+        eprintln!(
+            "warning: {path}:{line}:{col}: token \"{}\" does not occur in any sentence",
+            term.0
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use generator::{parse_language, sample::Sampler};
+
+    use super::alternative;
+
+    #[test]
+    fn labels_name_written_alternatives() {
+        let language = parse_language(include_str!("../../examples/calculator/calc.l1l")).unwrap();
+        let sampler = Sampler::new(&language, 0, 0).unwrap();
+        let coverage = sampler.cover("expr").unwrap();
+
+        let labels: Vec<_> = coverage
+            .cases
+            .iter()
+            .flat_map(|case| case.rules.iter())
+            .filter_map(|(nonterm, r)| alternative(&language, nonterm, *r))
+            .map(|(label, _)| label)
+            .collect();
+
+        assert!(coverage.uncovered.is_empty());
+        assert!(labels.contains(&r#"prod 1: ("*" | "/") 2: "/""#.to_owned()));
+        assert!(labels.contains(&r#"expr 1: (("+" | "-") prod)* 2: ε"#.to_owned()));
+        assert!(labels
+            .iter()
+            .all(|label| !label.contains("_group") && !label.contains("_star")));
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use generator::{
+    ast::{FirstSet, Language, NonTerm},
+    lexer::show,
+};
+
+use super::{Args, MessageFormat, Result, MESSAGE_FORMAT};
+
+fn print_sets(language: &Language, sets: &HashMap<NonTerm, FirstSet>, none: &str) {
+    for def in language.grammar.nonterms.iter() {
+        let mut terms: Vec<_> = sets.get(&def.name).into_iter().flatten().collect();
+        terms.sort();
+
+        let terms: Vec<_> = terms.into_iter().map(|term| show(term, none)).collect();
+        println!("{}: {}", def.name.0, terms.join(" "));
+    }
+}
+
+pub fn first(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &[])?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(args.single()?)?;
+
+    print_sets(&language, &language.grammar.build_first(), "ε");
+    Ok(())
+}
+
+pub fn follow(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &[])?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(args.single()?)?;
+
+    let fst = language.grammar.build_first();
+    print_sets(&language, &language.grammar.build_follow(&fst), "$");
+    Ok(())
+}
+
+pub fn table(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &[])?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(args.single()?)?;
+    let table = language.grammar.predict_table();
+
+    for def in language.grammar.nonterms.iter() {
+        println!("{}:", def.name.0);
+
+        for (term, rules) in table.get(&def.name).into_iter().flatten() {
+            let arms: Vec<_> = rules.iter().map(|&i| def.rules[i].arm()).collect();
+
+            let marker = if arms.len() > 1 { "  # conflict" } else { "" };
+            println!("    {} => {}{marker}", show(term, "$"), arms.join(" | "));
+        }
+    }
+
+    Ok(())
+}
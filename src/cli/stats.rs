@@ -0,0 +1,18 @@
+use generator::stats::Stats;
+
+use super::{export::output, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+pub fn stats(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &["--format", "-o", "--output", MESSAGE_FORMAT], &[])?;
+    let path = args.single()?;
+    let (_, language) = MessageFormat::from_args(&args)?.load(path)?;
+    let stats = Stats::new(&language);
+
+    let text = match args.value(&["--format"]).unwrap_or("text") {
+        "text" => stats.to_string(),
+        "json" => format!("{:#}\n", stats.to_json()),
+        other => return Err(Error::Usage(format!("unknown stats format {other}"))),
+    };
+
+    output(&args, &text)
+}
@@ -0,0 +1,99 @@
+use std::collections::HashMap;
+
+use generator::{
+    ast::{Expect, Language, NonTerm},
+    diagnostics::line_col,
+    interpret::Interpreter,
+};
+
+use super::{Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+#[derive(Default)]
+struct Summary {
+    passed: usize,
+    failed: usize,
+}
+
+fn run(path: &str, source: &str, language: &Language, summary: &mut Summary) {
+    let mut counts: HashMap<&NonTerm, usize> = HashMap::new();
+
+    for group in language.tests.iter().flat_map(|tests| tests.groups.iter()) {
+        let interpreter = Interpreter::new(language, &group.nonterm.0);
+
+        for case in group.cases.iter() {
+            let count = counts.entry(&group.nonterm).or_default();
+            *count += 1;
+
+            let (line, col) = line_col(source, case.span.start);
+            let name = format!("{path}:{line}:{col}: {}_{count}", group.nonterm.0);
+
+            let parsed = interpreter
+                .as_ref()
+                .map_err(Clone::clone)
+                .and_then(|interpreter| interpreter.parse(&case.input));
+            let failure = match (&case.expect, parsed) {
+                (Expect::Reject, Ok(_)) => {
+                    Some("expected a rejection, but the input parses".to_owned())
+                }
+                (Expect::Reject, Err(_)) | (Expect::Accept | Expect::Value(_), Ok(_)) => None,
+                (Expect::Accept | Expect::Value(_), Err(e)) => Some(match e.position() {
+                    Some(position) => format!("{e} at offset {position}"),
+                    None => e.to_string(),
+                }),
+            };
+
+            match failure {
+                None => {
+                    println!("{name} ... ok");
+                    summary.passed += 1;
+                }
+                Some(message) => {
+                    println!("{name} ... FAILED: {message}");
+                    summary.failed += 1;
+                }
+            }
+        }
+    }
+}
+
+pub fn test(args: &[String]) -> Result<()> {
+    let args = Args::parse(args, &[MESSAGE_FORMAT], &[])?;
+    let format = MessageFormat::from_args(&args)?;
+
+    if args.free.is_empty() {
+        return Err(Error::Usage("missing grammar file".to_owned()));
+    }
+
+    let mut summary = Summary::default();
+    let mut failed = false;
+
+    for path in args.free.iter() {
+        let checked = format.load(path).and_then(|(source, language)| {
+            format.report(path, &source, &language.diagnostics())?;
+            run(path, &source, &language, &mut summary);
+            Ok(())
+        });
+
+        match checked {
+            Ok(()) => {}
+            Err(Error::Grammar) => failed = true,
+            Err(e) => return Err(e),
+        }
+    }
+
+    if !failed || summary.passed + summary.failed > 0 {
+        let result = if summary.failed == 0 { "ok" } else { "FAILED" };
+        println!(
+            "\ntest result: {result}. {} passed; {} failed",
+            summary.passed, summary.failed
+        );
+    }
+
+    if failed {
+        Err(Error::Grammar)
+    } else if summary.failed > 0 {
+        Err(Error::TestsFailed(summary.failed))
+    } else {
+        Ok(())
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+use generator::build::find_grammars;
+
+use super::{generate, Args, Error, MessageFormat, Result, MESSAGE_FORMAT};
+
+struct Watch {
+    root: PathBuf,
+    out_dir: Option<PathBuf>,
+    module: String,
+    format: MessageFormat,
+}
+
+impl Watch {
+    fn grammars(&self) -> Result<Vec<PathBuf>> {
+        if self.root.is_file() {
+            return Ok(vec![self.root.clone()]);
+        }
+
+        let mut grammars = Vec::new();
+        find_grammars(&self.root, &mut grammars)
+            .map_err(|e| Error::Io(self.root.display().to_string(), e))?;
+        grammars.sort();
+        Ok(grammars)
+    }
+
+    fn output(&self, grammar: &Path) -> PathBuf {
+        let out = grammar.with_extension("rs");
+
+        match &self.out_dir {
+            Some(dir) => {
+                let base = if self.root.is_file() {
+                    self.root.parent().unwrap_or(Path::new(""))
+                } else {
+                    &self.root
+                };
+
+                dir.join(out.strip_prefix(base).unwrap_or(&out))
+            }
+            None => out,
+        }
+    }
+
+    fn regenerate(&self, grammar: &Path) -> Result<()> {
+        let generated =
+            generate::compile(&grammar.display().to_string(), &self.module, self.format)?;
+        let out = self.output(grammar);
+
+        if fs::read_to_string(&out).ok().as_ref() != Some(&generated) {
+            if let Some(parent) = out.parent() {
+                fs::create_dir_all(parent)
+                    .map_err(|e| Error::Io(parent.display().to_string(), e))?;
+            }
+
+            super::write(&out, &generated)?;
+        }
+
+        eprintln!("generated {} -> {}", grammar.display(), out.display());
+        Ok(())
+    }
+}
+
+pub fn watch(args: &[String]) -> Result<()> {
+    let args = Args::parse(
+        args,
+        &["--out-dir", "--module", "--interval", MESSAGE_FORMAT],
+        &[],
+    )?;
+
+    let module = args.value(&["--module"]).unwrap_or("parser");
+
+    if !generate::is_ident(module) {
+        return Err(Error::Usage(format!("invalid module name {module}")));
+    }
+
+    let interval = match args.value(&["--interval"]) {
+        Some(ms) => ms
+            .parse()
+            .map_err(|_| Error::Usage(format!("invalid interval {ms}")))?,
+        None => 500,
+    };
+
+    let watch = Watch {
+        root: PathBuf::from(args.single()?),
+        out_dir: args.value(&["--out-dir"]).map(PathBuf::from),
+        module: module.to_owned(),
+        format: MessageFormat::from_args(&args)?,
+    };
+
+    let mut seen: HashMap<PathBuf, SystemTime> = HashMap::new();
+    let mut grammars = watch.grammars()?;
+
+    loop {
+        seen.retain(|path, _| grammars.contains(path));
+
+        for grammar in grammars.iter() {
+            let Ok(modified) = fs::metadata(grammar).and_then(|meta| meta.modified()) else {
+                continue;
+            };
+
+            if seen.get(grammar) == Some(&modified) {
+                continue;
+            }
+
+            seen.insert(grammar.clone(), modified);
+
+            match watch.regenerate(grammar) {
+                Ok(()) | Err(Error::Grammar) => {}
+                Err(e) => eprintln!("error: {e}"),
+            }
+        }
+
+        thread::sleep(Duration::from_millis(interval));
+
+        // A directory that cannot be read for a moment, e.g. while an editor
+        // replaces files, keeps the grammars found last time.
+        match watch.grammars() {
+            Ok(found) => grammars = found,
+            Err(e) => eprintln!("error: {e}"),
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::Write,
+};
+
+use crate::{
+    ast::{Language, NonTerm, NonTermDef, Rule, Span},
+    diagnostics::line_col,
+    export::escape,
+    interpret::ParseTree,
+    lexer::Term,
+};
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+table { border-collapse: collapse; font-family: monospace; }
+td { padding: 0 0.5em; white-space: pre; vertical-align: top; }
+td.line, td.hits { color: #888; text-align: right; }
+tr.hit td { background: #dfe; }
+tr.miss td { background: #fdd; }
+a { color: inherit; }
+";
+
+struct Alternative<'l> {
+    rule: &'l Rule,
+    label: String,
+    hits: usize,
+}
+
+pub struct Coverage<'l> {
+    language: &'l Language,
+    index: HashMap<&'l NonTerm, usize>,
+    rules: Vec<Vec<usize>>,
+    terms: HashMap<&'l Term, usize>,
+}
+
+impl<'l> Coverage<'l> {
+    pub fn new(language: &'l Language) -> Self {
+        let mut index = HashMap::new();
+
+        for (i, def) in language.grammar.nonterms.iter().enumerate() {
+            index.entry(&def.name).or_insert(i);
+        }
+
+        Coverage {
+            language,
+            index,
+            rules: language
+                .grammar
+                .nonterms
+                .iter()
+                .map(|def| vec![0; def.rules.len()])
+                .collect(),
+            terms: language
+                .lexer
+                .mapping
+                .iter()
+                .map(|(term, _, _)| (term, 0))
+                .collect(),
+        }
+    }
+
+    pub fn add(&mut self, tree: &ParseTree) {
+        match tree {
+            ParseTree::Node {
+                nonterm,
+                rule,
+                children,
+            } => {
+                if let Some(hits) = self
+                    .index
+                    .get(nonterm)
+                    .and_then(|i| self.rules[*i].get_mut(*rule))
+                {
+                    *hits += 1;
+                }
+
+                for child in children {
+                    self.add(child);
+                }
+            }
+            ParseTree::Leaf { term, .. } => {
+                if let Some(hits) = self.terms.get_mut(term) {
+                    *hits += 1;
+                }
+            }
+        }
+    }
+
+    fn written(&self) -> impl Iterator<Item = (&'l NonTermDef, &[usize])> + '_ {
+        let written: HashSet<_> = self
+            .language
+            .grammar
+            .written()
+            .into_iter()
+            .map(|def| &def.name)
+            .collect();
+
+        self.language
+            .grammar
+            .nonterms
+            .iter()
+            .zip(self.rules.iter())
+            .filter(move |(def, _)| written.contains(&def.name))
+            .map(|(def, hits)| (def, &hits[..]))
+    }
+
+    // Written alternatives in grammar order, each followed by the choices
+    // made inside it: the alternatives of the helpers desugared from its
+    // groups and repetitions, labelled by the written item.
+    fn alternatives(&self) -> Vec<Alternative<'l>> {
+        let grammar = &self.language.grammar;
+        let mut helpers: HashMap<_, Vec<_>> = HashMap::new();
+
+        for (def, hits) in grammar.nonterms.iter().zip(self.rules.iter()) {
+            if def.helper.is_none() || def.rules.len() < 2 {
+                continue;
+            }
+
+            for (r, hits) in hits.iter().enumerate() {
+                let (owner, o) = grammar.owning_rule(def, r);
+
+                if owner.name != def.name {
+                    helpers
+                        .entry((&owner.name, o))
+                        .or_default()
+                        .push(Alternative {
+                            rule: &owner.rules[o],
+                            label: grammar.label(def, r),
+                            hits: *hits,
+                        });
+                }
+            }
+        }
+
+        let mut alternatives = Vec::new();
+
+        for (def, hits) in self.written() {
+            for (r, (rule, hits)) in def.rules.iter().zip(hits.iter()).enumerate() {
+                alternatives.push(Alternative {
+                    rule,
+                    label: def.label(r),
+                    hits: *hits,
+                });
+                alternatives.extend(helpers.remove(&(&def.name, r)).into_iter().flatten());
+            }
+        }
+
+        alternatives
+    }
+
+    pub fn term_hits(&self) -> impl Iterator<Item = (&'l Term, Span, usize)> + '_ {
+        self.language
+            .lexer
+            .mapping
+            .iter()
+            .map(|(term, _, span)| (term, *span, self.terms[term]))
+    }
+
+    fn line_hits(&self, source: &str) -> BTreeMap<usize, Vec<usize>> {
+        let mut lines: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+
+        for alternative in self.alternatives() {
+            let (line, _) = line_col(source, alternative.rule.span.start);
+            lines.entry(line).or_default().push(alternative.hits);
+        }
+
+        for (_, span, hits) in self.term_hits() {
+            let (line, _) = line_col(source, span.start);
+            lines.entry(line).or_default().push(hits);
+        }
+
+        lines
+    }
+
+    fn summary(&self) -> String {
+        let alternatives = self.alternatives();
+        let rules = alternatives.len();
+        let rules_hit = alternatives.iter().filter(|alt| alt.hits > 0).count();
+        let terms = self.term_hits().count();
+        let terms_hit = self.term_hits().filter(|(_, _, hits)| *hits > 0).count();
+
+        format!("{rules_hit}/{rules} alternatives and {terms_hit}/{terms} tokens used")
+    }
+
+    pub fn text(&self, path: &str, source: &str) -> String {
+        let mut out = "alternatives:\n".to_owned();
+
+        for Alternative { rule, label, hits } in self.alternatives() {
+            let (line, col) = line_col(source, rule.span.start);
+            let _ = writeln!(out, "{hits:>8}  {path}:{line}:{col}  {label}");
+        }
+
+        out.push_str("tokens:\n");
+
+        for (term, span, hits) in self.term_hits() {
+            let (line, col) = line_col(source, span.start);
+            let _ = writeln!(out, "{hits:>8}  {path}:{line}:{col}  \"{}\"", term.0);
+        }
+
+        out.push_str(&self.summary());
+        out.push('\n');
+        out
+    }
+
+    pub fn lcov(&self, path: &str, source: &str) -> String {
+        let mut out = format!("TN:\nSF:{path}\n");
+        let defs: Vec<_> = self.written().collect();
+
+        for (def, _) in defs.iter() {
+            let (line, _) = line_col(source, def.name_span.start);
+            let _ = writeln!(out, "FN:{line},{}", def.name.0);
+        }
+
+        for (def, hits) in defs.iter() {
+            let _ = writeln!(out, "FNDA:{},{}", hits.iter().sum::<usize>(), def.name.0);
+        }
+
+        let _ = writeln!(out, "FNF:{}", defs.len());
+        let _ = writeln!(
+            out,
+            "FNH:{}",
+            defs.iter()
+                .filter(|(_, hits)| hits.iter().any(|h| *h > 0))
+                .count()
+        );
+
+        let alternatives = self.alternatives();
+        let mut branches: BTreeMap<usize, usize> = BTreeMap::new();
+
+        for alternative in alternatives.iter() {
+            let (line, _) = line_col(source, alternative.rule.span.start);
+            let branch = branches.entry(line).or_default();
+            let _ = writeln!(out, "BRDA:{line},0,{branch},{}", alternative.hits);
+            *branch += 1;
+        }
+
+        let _ = writeln!(out, "BRF:{}", alternatives.len());
+        let _ = writeln!(
+            out,
+            "BRH:{}",
+            alternatives.iter().filter(|alt| alt.hits > 0).count()
+        );
+
+        let lines = self.line_hits(source);
+
+        for (line, hits) in lines.iter() {
+            let count = if hits.contains(&0) {
+                0
+            } else {
+                hits.iter().sum()
+            };
+            let _ = writeln!(out, "DA:{line},{count}");
+        }
+
+        let _ = writeln!(out, "LF:{}", lines.len());
+        let _ = writeln!(
+            out,
+            "LH:{}",
+            lines.values().filter(|hits| !hits.contains(&0)).count()
+        );
+
+        out.push_str("end_of_record\n");
+        out
+    }
+
+    pub fn html(&self, source: &str, title: &str) -> String {
+        let mut out = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n<h1>{}</h1>\n<p>{}</p>\n",
+            escape(title),
+            escape(title),
+            escape(&self.summary())
+        );
+
+        let unused: Vec<_> = self
+            .alternatives()
+            .into_iter()
+            .filter(|alt| alt.hits == 0)
+            .map(|alt| (line_col(source, alt.rule.span.start).0, alt.label))
+            .chain(
+                self.term_hits()
+                    .filter(|(_, _, hits)| *hits == 0)
+                    .map(|(term, span, _)| {
+                        (line_col(source, span.start).0, format!("\"{}\"", term.0))
+                    }),
+            )
+            .collect();
+
+        if !unused.is_empty() {
+            out.push_str("<h2>Never used</h2>\n<ul>\n");
+
+            for (line, label) in unused {
+                let _ = writeln!(out, "<li><a href=\"#L{line}\">{}</a></li>", escape(&label));
+            }
+
+            out.push_str("</ul>\n");
+        }
+
+        let lines = self.line_hits(source);
+        out.push_str("<table>\n");
+
+        for (i, text) in source.lines().enumerate() {
+            let line = i + 1;
+            let (class, hits) = match lines.get(&line) {
+                Some(hits) => (
+                    if hits.contains(&0) {
+                        " class=\"miss\""
+                    } else {
+                        " class=\"hit\""
+                    },
+                    hits.iter()
+                        .map(usize::to_string)
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                ),
+                None => ("", String::new()),
+            };
+
+            let _ = writeln!(
+                out,
+                "<tr id=\"L{line}\"{class}><td class=\"line\">{line}</td><td class=\"hits\">{hits}</td><td>{}</td></tr>",
+                escape(text)
+            );
+        }
+
+        out.push_str("</table>\n</body>\n</html>\n");
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::Coverage;
+    use crate::parse_language;
+
+    const CALC: &str = include_str!("../examples/calculator/calc.l1l");
+
+    #[test]
+    fn reports_written_alternatives() {
+        let language = parse_language(CALC).unwrap();
+        let mut coverage = Coverage::new(&language);
+        coverage.add(&language.interpret("expr", "1 * 2").unwrap());
+
+        let text = coverage.text("calc.l1l", CALC);
+        let alternatives: Vec<_> = text
+            .lines()
+            .skip(1)
+            .take_while(|line| *line != "tokens:")
+            .map(|line| line.split("  ").last().unwrap())
+            .collect();
+
+        assert_eq!(
+            alternatives,
+            [
+                r#"atom 1: "lp" expr "rp""#,
+                r#"atom 2: "n""#,
+                r#"prod 1: atom (("*" | "/") atom)*"#,
+                r#"prod 1: ("*" | "/") 1: "*""#,
+                r#"prod 1: ("*" | "/") 2: "/""#,
+                r#"prod 1: (("*" | "/") atom)* 1: (("*" | "/") atom) (("*" | "/") atom)*"#,
+                r#"prod 1: (("*" | "/") atom)* 2: ε"#,
+                r#"expr 1: prod (("+" | "-") prod)*"#,
+                r#"expr 1: ("+" | "-") 1: "+""#,
+                r#"expr 1: ("+" | "-") 2: "-""#,
+                r#"expr 1: (("+" | "-") prod)* 1: (("+" | "-") prod) (("+" | "-") prod)*"#,
+                r#"expr 1: (("+" | "-") prod)* 2: ε"#,
+            ]
+        );
+        assert!(text.ends_with("7/12 alternatives and 2/7 tokens used\n"));
+    }
+
+    #[test]
+    fn lcov_branches_are_unique() {
+        let language = parse_language(CALC).unwrap();
+        let coverage = Coverage::new(&language);
+        let lcov = coverage.lcov("calc.l1l", CALC);
+        let branches: Vec<_> = lcov
+            .lines()
+            .filter_map(|line| line.strip_prefix("BRDA:"))
+            .map(|line| line.rsplit_once(',').unwrap().0)
+            .collect();
+
+        assert_eq!(
+            branches.iter().collect::<HashSet<_>>().len(),
+            branches.len()
+        );
+        assert_eq!(
+            lcov.lines().filter(|line| line.starts_with("FN:")).count(),
+            3
+        );
+        assert!(lcov.contains(&format!("BRF:{}\n", branches.len())));
+    }
+}
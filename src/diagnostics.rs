@@ -0,0 +1,370 @@
+use std::{error::Error, fmt};
+
+use lalrpop_util::ParseError;
+use serde_json::{json, Value};
+
+use crate::{
+    ast::{Conflict, NonTerm, Span},
+    lexer::Term,
+    notation::{lexer::LexicalError, tokens::Token},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Diagnostic {
+    Syntax {
+        span: Span,
+        message: String,
+    },
+    DuplicateNonTerm {
+        nonterm: NonTerm,
+        span: Span,
+    },
+    UndefinedNonTerm {
+        nonterm: NonTerm,
+        used_in: NonTerm,
+        span: Span,
+    },
+    UndefinedTerm {
+        term: Term,
+        used_in: NonTerm,
+        span: Span,
+    },
+    InvalidCode {
+        context: String,
+        message: String,
+        span: Option<Span>,
+    },
+    Conflict {
+        nonterm: NonTerm,
+        item: Option<String>,
+        arms: (String, String),
+        spans: (Span, Span),
+        tokens: Vec<Option<Term>>,
+    },
+    Unreachable {
+        nonterm: NonTerm,
+        span: Span,
+    },
+    InvalidTest {
+        nonterm: NonTerm,
+        message: String,
+        span: Span,
+    },
+}
+
+fn expected_list(expected: &[String]) -> String {
+    match expected {
+        [] => String::new(),
+        [one] => format!(", expected {one}"),
+        _ => format!(", expected one of {}", expected.join(", ")),
+    }
+}
+
+impl From<ParseError<usize, Token, LexicalError>> for Diagnostic {
+    fn from(err: ParseError<usize, Token, LexicalError>) -> Self {
+        match err {
+            ParseError::InvalidToken { location } => Diagnostic::Syntax {
+                span: Span {
+                    start: location,
+                    end: location,
+                },
+                message: "invalid token".to_owned(),
+            },
+            ParseError::UnrecognizedEof { location, expected } => Diagnostic::Syntax {
+                span: Span {
+                    start: location,
+                    end: location,
+                },
+                message: format!("unexpected end of file{}", expected_list(&expected)),
+            },
+            ParseError::UnrecognizedToken {
+                token: (start, tok, end),
+                expected,
+            } => Diagnostic::Syntax {
+                span: Span { start, end },
+                message: format!("unexpected token {tok}{}", expected_list(&expected)),
+            },
+            ParseError::ExtraToken {
+                token: (start, tok, end),
+            } => Diagnostic::Syntax {
+                span: Span { start, end },
+                message: format!("extra token {tok}"),
+            },
+            ParseError::User {
+                error: LexicalError::InvalidToken { start, end },
+            } => Diagnostic::Syntax {
+                span: Span { start, end },
+                message: "invalid token".to_owned(),
+            },
+        }
+    }
+}
+
+impl From<Conflict<'_>> for Diagnostic {
+    fn from(conflict: Conflict<'_>) -> Self {
+        Diagnostic::Conflict {
+            nonterm: conflict.nonterm,
+            item: conflict.item,
+            arms: (conflict.arms.0.arm(), conflict.arms.1.arm()),
+            spans: (conflict.arms.0.span, conflict.arms.1.span),
+            tokens: conflict.tokens,
+        }
+    }
+}
+
+fn show_term(term: &Option<Term>) -> Value {
+    match term {
+        Some(term) => Value::String(term.0.clone()),
+        None => Value::Null,
+    }
+}
+
+impl Diagnostic {
+    pub fn severity(&self) -> Severity {
+        match self {
+            Diagnostic::Unreachable { .. } => Severity::Warning,
+            _ => Severity::Error,
+        }
+    }
+
+    pub fn code(&self) -> &'static str {
+        match self {
+            Diagnostic::Syntax { .. } => "syntax-error",
+            Diagnostic::DuplicateNonTerm { .. } => "duplicate-nonterminal",
+            Diagnostic::UndefinedNonTerm { .. } => "undefined-nonterminal",
+            Diagnostic::UndefinedTerm { .. } => "undefined-token",
+            Diagnostic::InvalidCode { .. } => "invalid-code",
+            Diagnostic::Conflict { .. } => "ll1-conflict",
+            Diagnostic::Unreachable { .. } => "unreachable-nonterminal",
+            Diagnostic::InvalidTest { .. } => "invalid-test",
+        }
+    }
+
+    pub fn spans(&self) -> Vec<Span> {
+        match self {
+            Diagnostic::Syntax { span, .. }
+            | Diagnostic::DuplicateNonTerm { span, .. }
+            | Diagnostic::UndefinedNonTerm { span, .. }
+            | Diagnostic::UndefinedTerm { span, .. }
+            | Diagnostic::Unreachable { span, .. }
+            | Diagnostic::InvalidTest { span, .. } => vec![*span],
+            Diagnostic::InvalidCode { span, .. } => span.iter().copied().collect(),
+            Diagnostic::Conflict { spans, .. } => vec![spans.0, spans.1],
+        }
+    }
+
+    pub fn span(&self) -> Option<Span> {
+        self.spans().first().copied()
+    }
+
+    pub fn nonterm(&self) -> Option<&NonTerm> {
+        match self {
+            Diagnostic::Syntax { .. } | Diagnostic::InvalidCode { .. } => None,
+            Diagnostic::DuplicateNonTerm { nonterm, .. }
+            | Diagnostic::Conflict { nonterm, .. }
+            | Diagnostic::Unreachable { nonterm, .. }
+            | Diagnostic::InvalidTest { nonterm, .. } => Some(nonterm),
+            Diagnostic::UndefinedNonTerm { used_in, .. }
+            | Diagnostic::UndefinedTerm { used_in, .. } => Some(used_in),
+        }
+    }
+
+    pub fn to_json(&self, file: &str, source: &str) -> Value {
+        let spans: Vec<_> = self
+            .spans()
+            .into_iter()
+            .map(|span| {
+                let (line, column) = line_col(source, span.start);
+                let (end_line, end_column) = line_col(source, span.end);
+
+                json!({
+                    "start": span.start,
+                    "end": span.end,
+                    "line": line,
+                    "column": column,
+                    "end_line": end_line,
+                    "end_column": end_column,
+                })
+            })
+            .collect();
+
+        let (alternatives, tokens) = match self {
+            Diagnostic::Conflict { arms, tokens, .. } => (
+                vec![arms.0.clone(), arms.1.clone()],
+                tokens.iter().map(show_term).collect(),
+            ),
+            Diagnostic::UndefinedTerm { term, .. } => {
+                (Vec::new(), vec![Value::String(term.0.clone())])
+            }
+            _ => (Vec::new(), Vec::new()),
+        };
+
+        json!({
+            "file": file,
+            "severity": self.severity().to_string(),
+            "code": self.code(),
+            "message": self.to_string(),
+            "nonterminal": self.nonterm().map(|nt| &nt.0),
+            "alternatives": alternatives,
+            "tokens": tokens,
+            "spans": spans,
+        })
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Diagnostic::Syntax { message, .. } => write!(f, "{message}"),
+            Diagnostic::DuplicateNonTerm { nonterm, .. } => {
+                write!(f, "nonterminal {} is defined more than once", nonterm.0)
+            }
+            Diagnostic::UndefinedNonTerm {
+                nonterm, used_in, ..
+            } => write!(
+                f,
+                "undefined nonterminal {} used in rules for {}",
+                nonterm.0, used_in.0
+            ),
+            Diagnostic::UndefinedTerm { term, used_in, .. } => write!(
+                f,
+                "undefined token \"{}\" used in rules for {}",
+                term.0, used_in.0
+            ),
+            Diagnostic::InvalidCode {
+                context, message, ..
+            } => {
+                write!(f, "invalid code in {context}: {message}")
+            }
+            Diagnostic::Conflict {
+                nonterm,
+                item,
+                arms,
+                tokens,
+                ..
+            } => {
+                match item {
+                    Some(item) => {
+                        writeln!(f, "ll(1) conflict in {item} in rules for {}", nonterm.0)?
+                    }
+                    None => writeln!(f, "ll(1) conflict in rules for {}", nonterm.0)?,
+                }
+
+                writeln!(f, "Arm1: {}", arms.0)?;
+                writeln!(f, "Arm2: {}", arms.1)?;
+                write!(f, "Both arms are predicted by:")?;
+
+                for token in tokens {
+                    match token {
+                        Some(term) => write!(f, " \"{}\"", term.0)?,
+                        None => write!(f, " end of input")?,
+                    }
+                }
+
+                Ok(())
+            }
+            Diagnostic::Unreachable { nonterm, .. } => write!(
+                f,
+                "nonterminal {} is not reachable from any pub nonterminal",
+                nonterm.0
+            ),
+            Diagnostic::InvalidTest { message, .. } => write!(f, "{message}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Diagnostics(pub Vec<Diagnostic>);
+
+impl Diagnostics {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Diagnostic> {
+        self.0.iter()
+    }
+
+    pub fn push(&mut self, diagnostic: impl Into<Diagnostic>) {
+        self.0.push(diagnostic.into());
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.iter().any(|d| d.severity() == Severity::Error)
+    }
+
+    pub fn render(&self, path: &str, source: &str) -> String {
+        let mut out = String::new();
+
+        for diagnostic in self.iter() {
+            out.push_str(&format!("{}: ", diagnostic.severity()));
+
+            if let Some(span) = diagnostic.span() {
+                let (line, col) = line_col(source, span.start);
+                out.push_str(&format!("{path}:{line}:{col}: "));
+            } else {
+                out.push_str(&format!("{path}: "));
+            }
+
+            out.push_str(&diagnostic.to_string());
+            out.push('\n');
+        }
+
+        out
+    }
+}
+
+impl From<Diagnostic> for Diagnostics {
+    fn from(diagnostic: Diagnostic) -> Self {
+        Diagnostics(vec![diagnostic])
+    }
+}
+
+impl IntoIterator for Diagnostics {
+    type Item = Diagnostic;
+    type IntoIter = std::vec::IntoIter<Diagnostic>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.into_iter()
+    }
+}
+
+impl fmt::Display for Diagnostics {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for diagnostic in self.iter() {
+            writeln!(f, "{}: {diagnostic}", diagnostic.severity())?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for Diagnostics {}
+
+pub fn line_col(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let col = before
+        .rfind('\n')
+        .map_or(before, |nl| &before[nl + 1..])
+        .chars()
+        .count()
+        + 1;
+
+    (line, col)
+}
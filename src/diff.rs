@@ -0,0 +1,506 @@
+use std::collections::HashSet;
+
+use crate::{
+    ast::{first, FirstMap, Item, Language, Node, NonTermDef, Rule},
+    interpret::Interpreter,
+    lexer::{desc, show},
+    notation::format::code_lines,
+    sample::Sampler,
+};
+
+const SAMPLES: usize = 200;
+const SAMPLE_DEPTH: usize = 8;
+
+pub struct Section {
+    pub title: &'static str,
+    pub lines: Vec<String>,
+}
+
+type Binding<'r> = (Option<&'r String>, Option<Vec<String>>);
+
+fn node_binding(node: &Node) -> Binding<'_> {
+    match node {
+        Node::Term(_, name, _) => (name.as_ref(), None),
+        Node::NonTerm {
+            extract_name, args, ..
+        } => (extract_name.as_ref(), args.as_deref().map(code_lines)),
+    }
+}
+
+fn item_bindings<'r>(item: &'r Item, out: &mut Vec<Binding<'r>>) {
+    match item {
+        Item::Node(node) => out.push(node_binding(node)),
+        Item::Group { alts, .. } => {
+            out.push((item.extract_name(), None));
+
+            for item in alts.iter().flatten() {
+                item_bindings(item, out);
+            }
+        }
+        Item::Repeat { item: inner, .. } => {
+            out.push((item.extract_name(), None));
+            item_bindings(inner, out);
+        }
+        Item::Macro { args, .. } => {
+            out.push((item.extract_name(), None));
+
+            for item in args.iter() {
+                item_bindings(item, out);
+            }
+        }
+    }
+}
+
+fn bindings(rule: &Rule) -> Vec<Binding<'_>> {
+    match &rule.items {
+        Some(items) => {
+            let mut out = Vec::new();
+
+            for item in items.iter() {
+                item_bindings(item, &mut out);
+            }
+
+            out
+        }
+        None => rule.nodes.iter().map(node_binding).collect(),
+    }
+}
+
+fn find<'l>(language: &'l Language, def: &NonTermDef) -> Option<&'l NonTermDef> {
+    language
+        .grammar
+        .written()
+        .into_iter()
+        .find(|other| other.name == def.name)
+}
+
+fn pairs<'a>(old: &'a [Rule], new: &'a [Rule]) -> Vec<(Option<&'a Rule>, Option<&'a Rule>)> {
+    let mut unmatched: Vec<_> = new.iter().map(Some).collect();
+    let mut pairs = Vec::new();
+
+    for rule in old {
+        let key = rule.arm();
+        let other = unmatched
+            .iter_mut()
+            .find(|other| other.is_some_and(|other| other.arm() == key))
+            .and_then(Option::take);
+
+        pairs.push((Some(rule), other));
+    }
+
+    pairs.extend(
+        unmatched
+            .into_iter()
+            .flatten()
+            .map(|rule| (None, Some(rule))),
+    );
+    pairs
+}
+
+fn tokens(old: &Language, new: &Language) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for (term, old_desc, _) in old.lexer.mapping.iter() {
+        match new.lexer.mapping.iter().find(|(other, _, _)| other == term) {
+            None => lines.push(format!("- \"{}\" = {}", term.0, desc(old_desc))),
+            Some((_, new_desc, _)) if desc(old_desc) != desc(new_desc) => lines.push(format!(
+                "~ \"{}\": {} -> {}",
+                term.0,
+                desc(old_desc),
+                desc(new_desc)
+            )),
+            Some(_) => {}
+        }
+    }
+
+    for (term, new_desc, _) in new.lexer.mapping.iter() {
+        if !old.lexer.mapping.iter().any(|(other, _, _)| other == term) {
+            lines.push(format!("+ \"{}\" = {}", term.0, desc(new_desc)));
+        }
+    }
+
+    lines
+}
+
+fn nonterms(old: &Language, new: &Language) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for def in old.grammar.written() {
+        let Some(other) = find(new, def) else {
+            lines.push(format!("- {}", def.name.0));
+            continue;
+        };
+
+        let mut changes = Vec::new();
+
+        if def.is_pub != other.is_pub {
+            changes.push(if other.is_pub {
+                "now pub".to_owned()
+            } else {
+                "no longer pub".to_owned()
+            });
+        }
+
+        if def.args.as_deref().map(code_lines) != other.args.as_deref().map(code_lines) {
+            changes.push("arguments changed".to_owned());
+        }
+
+        if code_lines(&def.ret_ty) != code_lines(&other.ret_ty) {
+            changes.push(format!(
+                "type !{{ {} }}! -> !{{ {} }}!",
+                code_lines(&def.ret_ty).join(" "),
+                code_lines(&other.ret_ty).join(" ")
+            ));
+        }
+
+        for pair in pairs(&def.rules, &other.rules) {
+            match pair {
+                (Some(rule), None) => changes.push(format!("- {}", rule.arm())),
+                (None, Some(rule)) => changes.push(format!("+ {}", rule.arm())),
+                (Some(a), Some(b))
+                    if code_lines(&a.code) != code_lines(&b.code) || bindings(a) != bindings(b) =>
+                {
+                    changes.push(format!("~ {}: action or bindings changed", a.arm()))
+                }
+                _ => {}
+            }
+        }
+
+        if !changes.is_empty() {
+            lines.push(format!("~ {}", def.name.0));
+            lines.extend(changes.into_iter().map(|change| format!("    {change}")));
+        }
+    }
+
+    for def in new.grammar.written() {
+        if find(old, def).is_none() {
+            lines.push(format!("+ {}", def.name.0));
+        }
+    }
+
+    lines
+}
+
+fn sets(
+    old: &Language,
+    new: &Language,
+    old_sets: &FirstMap,
+    new_sets: &FirstMap,
+    none: &str,
+) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for def in old.grammar.written() {
+        if find(new, def).is_none() {
+            continue;
+        }
+
+        let empty = HashSet::new();
+        let before = old_sets.get(&def.name).unwrap_or(&empty);
+        let after = new_sets.get(&def.name).unwrap_or(&empty);
+
+        let mut added: Vec<_> = after.difference(before).collect();
+        let mut removed: Vec<_> = before.difference(after).collect();
+        added.sort();
+        removed.sort();
+
+        if added.is_empty() && removed.is_empty() {
+            continue;
+        }
+
+        let changes: Vec<_> = added
+            .into_iter()
+            .map(|term| format!("+{}", show(term, none)))
+            .chain(
+                removed
+                    .into_iter()
+                    .map(|term| format!("-{}", show(term, none))),
+            )
+            .collect();
+
+        lines.push(format!("{}: {}", def.name.0, changes.join(" ")));
+    }
+
+    lines
+}
+
+fn nullable(old: &Language, new: &Language, old_fst: &FirstMap, new_fst: &FirstMap) -> Vec<String> {
+    let mut lines = Vec::new();
+
+    for def in old.grammar.written() {
+        let Some(other) = find(new, def) else {
+            continue;
+        };
+
+        for pair in pairs(&def.rules, &other.rules) {
+            let (Some(a), Some(b)) = pair else {
+                continue;
+            };
+
+            let before = first(&a.nodes, old_fst).contains(&None);
+            let after = first(&b.nodes, new_fst).contains(&None);
+
+            if before != after {
+                let state = if after {
+                    "became nullable"
+                } else {
+                    "is no longer nullable"
+                };
+                lines.push(format!("{}: {} {state}", def.name.0, a.arm()));
+            }
+        }
+    }
+
+    lines
+}
+
+fn conflicts(old: &Language, new: &Language) -> Vec<String> {
+    let key = |language: &Language| -> Vec<String> {
+        language
+            .grammar
+            .conflicts()
+            .into_iter()
+            .map(|conflict| {
+                let place = match &conflict.item {
+                    Some(item) => format!("{item} in {}", conflict.nonterm.0),
+                    None => conflict.nonterm.0.clone(),
+                };
+
+                format!(
+                    "{place}: {} | {}",
+                    conflict.arms.0.arm(),
+                    conflict.arms.1.arm()
+                )
+            })
+            .collect()
+    };
+
+    let before = key(old);
+    let after = key(new);
+
+    after
+        .iter()
+        .filter(|conflict| !before.contains(conflict))
+        .map(|conflict| format!("+ {conflict}"))
+        .chain(
+            before
+                .iter()
+                .filter(|conflict| !after.contains(conflict))
+                .map(|conflict| format!("- {conflict}")),
+        )
+        .collect()
+}
+
+pub fn diff(old: &Language, new: &Language) -> Vec<Section> {
+    let old_fst = old.grammar.build_first();
+    let new_fst = new.grammar.build_first();
+    let old_flw = old.grammar.build_follow(&old_fst);
+    let new_flw = new.grammar.build_follow(&new_fst);
+
+    [
+        Section {
+            title: "tokens",
+            lines: tokens(old, new),
+        },
+        Section {
+            title: "nonterminals",
+            lines: nonterms(old, new),
+        },
+        Section {
+            title: "FIRST sets",
+            lines: sets(old, new, &old_fst, &new_fst, "ε"),
+        },
+        Section {
+            title: "FOLLOW sets",
+            lines: sets(old, new, &old_flw, &new_flw, "$"),
+        },
+        Section {
+            title: "nullable alternatives",
+            lines: nullable(old, new, &old_fst, &new_fst),
+        },
+        Section {
+            title: "LL(1) conflicts",
+            lines: conflicts(old, new),
+        },
+    ]
+    .into_iter()
+    .filter(|section| !section.lines.is_empty())
+    .collect()
+}
+
+fn candidates(language: &Language, start: &str) -> Vec<String> {
+    let Ok(mut sampler) = Sampler::new(language, SAMPLE_DEPTH, 0) else {
+        return Vec::new();
+    };
+
+    let mut inputs: Vec<_> = match sampler.cover(start) {
+        Ok(coverage) => coverage
+            .cases
+            .iter()
+            .map(|case| sampler.render(&case.terms))
+            .collect(),
+        Err(_) => return Vec::new(),
+    };
+
+    for _ in 0..SAMPLES {
+        if let Ok(sentence) = sampler.sentence(start) {
+            inputs.push(sentence);
+        }
+    }
+
+    inputs
+}
+
+fn interpreter<'l>(language: &'l Language, start: &str) -> Option<Interpreter<'l>> {
+    if !language.grammar.conflicts().is_empty() {
+        return None;
+    }
+
+    Interpreter::new(language, start).ok()
+}
+
+pub fn example(from: &Language, to: &Language, start: &str) -> Option<String> {
+    let accepts = interpreter(from, start)?;
+    let rejects = interpreter(to, start)?;
+
+    candidates(from, start)
+        .into_iter()
+        .filter(|input| {
+            accepts.parse(input).is_ok()
+                && rejects.parse(input).is_err_and(|e| e.position().is_some())
+        })
+        .min_by_key(|input| (input.len(), input.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{diff, example};
+    use crate::{ast::Language, parse_language};
+
+    fn language(rules: &str) -> Language {
+        parse_language(&format!(
+            r#"
+preamble = !{{ }}!
+
+tokens = {{
+    "n" = regex "[0-9]+",
+    "," = token ",",
+}}
+
+rules = {{
+{rules}
+}}
+"#
+        ))
+        .unwrap()
+    }
+
+    fn sections(old: &Language, new: &Language) -> Vec<(&'static str, Vec<String>)> {
+        diff(old, new)
+            .into_iter()
+            .map(|section| (section.title, section.lines))
+            .collect()
+    }
+
+    #[test]
+    fn reports_the_written_grammar() {
+        let old = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" more => !{ () }!,
+    }
+
+    more: !{ () }! = {
+        "," "n" more => !{ () }!,
+        => !{ () }!,
+    }
+"#,
+        );
+        let new = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" ("," "n")* ","? => !{ () }!,
+    }
+"#,
+        );
+
+        assert_eq!(
+            sections(&old, &new),
+            [
+                (
+                    "nonterminals",
+                    vec![
+                        "~ list".to_owned(),
+                        r#"    - "n" more"#.to_owned(),
+                        r#"    + "n" ("," "n")* ","?"#.to_owned(),
+                        "- more".to_owned(),
+                    ]
+                ),
+                (
+                    "LL(1) conflicts",
+                    vec![r#"+ ("," "n")* in list: ("," "n") ("," "n")* | ε"#.to_owned()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn compares_bindings_inside_items() {
+        let old = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" ("," "n")* => !{ () }!,
+    }
+"#,
+        );
+        let new = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" ("," <v:"n">)* => !{ () }!,
+    }
+"#,
+        );
+
+        assert_eq!(
+            sections(&old, &new),
+            [(
+                "nonterminals",
+                vec![
+                    "~ list".to_owned(),
+                    r#"    ~ "n" ("," "n")*: action or bindings changed"#.to_owned(),
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn finds_examples_only_for_ll1_grammars() {
+        let short = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" => !{ () }!,
+    }
+"#,
+        );
+        let long = language(
+            r#"
+    pub list: !{ () }! = {
+        "n" ("," "n")* => !{ () }!,
+    }
+"#,
+        );
+        let left = language(
+            r#"
+    pub list: !{ () }! = {
+        list "," "n" => !{ () }!,
+        "n" => !{ () }!,
+    }
+"#,
+        );
+
+        assert!(example(&long, &short, "list").is_some());
+        assert_eq!(example(&short, &long, "list"), None);
+        assert_eq!(example(&left, &long, "list"), None);
+        assert_eq!(example(&long, &left, "list"), None);
+    }
+}
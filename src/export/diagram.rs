@@ -0,0 +1,313 @@
+use std::fmt::Write;
+
+use super::escape;
+use crate::{
+    ast::{self, Language, Node, NonTermDef, Repeat, Rule},
+    lexer::TokDesc,
+};
+
+const CHAR_WIDTH: f64 = 8.5;
+const BOX_HEIGHT: f64 = 24.0;
+const H_GAP: f64 = 12.0;
+const V_GAP: f64 = 10.0;
+const ARC: f64 = 20.0;
+const PADDING: f64 = 20.0;
+
+enum Item {
+    Terminal { label: String, title: String },
+    NonTerminal { name: String },
+    Sequence(Vec<Item>),
+    Choice(Vec<Item>),
+    Loop(Box<Item>),
+    Skip,
+}
+
+impl Item {
+    fn boxed_width(label: &str) -> f64 {
+        label.chars().count() as f64 * CHAR_WIDTH + 20.0
+    }
+
+    fn width(&self) -> f64 {
+        match self {
+            Item::Terminal { label, .. } => Item::boxed_width(label),
+            Item::NonTerminal { name } => Item::boxed_width(name),
+            Item::Sequence(items) => {
+                let gaps = items.len().saturating_sub(1) as f64 * H_GAP;
+                items.iter().map(Item::width).sum::<f64>() + gaps
+            }
+            Item::Choice(items) => items.iter().map(Item::width).fold(0.0, f64::max) + 2.0 * ARC,
+            Item::Loop(item) => item.width() + 2.0 * ARC,
+            Item::Skip => 0.0,
+        }
+    }
+
+    fn up(&self) -> f64 {
+        match self {
+            Item::Terminal { .. } | Item::NonTerminal { .. } => BOX_HEIGHT / 2.0,
+            Item::Sequence(items) => items.iter().map(Item::up).fold(0.0, f64::max),
+            Item::Choice(items) => items.first().map_or(0.0, Item::up),
+            Item::Loop(item) => item.up(),
+            Item::Skip => 0.0,
+        }
+    }
+
+    fn down(&self) -> f64 {
+        match self {
+            Item::Terminal { .. } | Item::NonTerminal { .. } => BOX_HEIGHT / 2.0,
+            Item::Sequence(items) => items.iter().map(Item::down).fold(0.0, f64::max),
+            Item::Choice(items) => {
+                let offsets = Item::offsets(items);
+                items
+                    .last()
+                    .zip(offsets.last())
+                    .map_or(0.0, |(item, offset)| offset + item.down())
+            }
+            Item::Loop(item) => item.down() + V_GAP,
+            Item::Skip => 0.0,
+        }
+    }
+
+    fn offsets(items: &[Item]) -> Vec<f64> {
+        let mut offsets = Vec::with_capacity(items.len());
+        let mut y = 0.0;
+
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                y += items[i - 1].down() + V_GAP + item.up();
+            }
+
+            offsets.push(y);
+        }
+
+        offsets
+    }
+
+    fn render_box(out: &mut String, x: f64, y: f64, label: &str, class: &str, rx: f64) {
+        let width = Item::boxed_width(label);
+
+        let _ = write!(
+            out,
+            r#"<rect class="{class}" x="{x}" y="{}" width="{width}" height="{BOX_HEIGHT}" rx="{rx}"/><text x="{}" y="{}">{}</text>"#,
+            y - BOX_HEIGHT / 2.0,
+            x + width / 2.0,
+            y + 4.0,
+            escape(label)
+        );
+    }
+
+    fn line(out: &mut String, x1: f64, x2: f64, y: f64) {
+        if x2 > x1 {
+            let _ = write!(out, r#"<path d="M{x1} {y}H{x2}"/>"#);
+        }
+    }
+
+    fn render(&self, out: &mut String, x: f64, y: f64) {
+        match self {
+            Item::Terminal { label, title } => {
+                let _ = write!(out, "<g><title>{}</title>", escape(title));
+                Item::render_box(out, x, y, label, "terminal", BOX_HEIGHT / 2.0);
+                out.push_str("</g>");
+            }
+            Item::NonTerminal { name } => {
+                let _ = write!(out, r##"<a href="#{}">"##, escape(name));
+                Item::render_box(out, x, y, name, "nonterminal", 0.0);
+                out.push_str("</a>");
+            }
+            Item::Sequence(items) => {
+                let mut x = x;
+
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        Item::line(out, x, x + H_GAP, y);
+                        x += H_GAP;
+                    }
+
+                    item.render(out, x, y);
+                    x += item.width();
+                }
+            }
+            Item::Choice(items) => {
+                let inner = self.width() - 2.0 * ARC;
+                let right = x + ARC + inner;
+
+                for (item, offset) in items.iter().zip(Item::offsets(items)) {
+                    let alt = y + offset;
+
+                    if offset == 0.0 {
+                        Item::line(out, x, x + ARC, y);
+                        Item::line(out, right, right + ARC, y);
+                    } else {
+                        let _ = write!(
+                            out,
+                            r#"<path d="M{x} {y}C{} {y} {} {alt} {} {alt}"/><path d="M{right} {alt}C{} {alt} {} {y} {} {y}"/>"#,
+                            x + ARC / 2.0,
+                            x + ARC / 2.0,
+                            x + ARC,
+                            right + ARC / 2.0,
+                            right + ARC / 2.0,
+                            right + ARC
+                        );
+                    }
+
+                    item.render(out, x + ARC, alt);
+                    Item::line(out, x + ARC + item.width(), right, alt);
+                }
+            }
+            Item::Loop(item) => {
+                let left = x + ARC;
+                let right = left + item.width();
+                let back = y + item.down() + V_GAP;
+
+                Item::line(out, x, left, y);
+                item.render(out, left, y);
+                Item::line(out, right, right + ARC, y);
+                let _ = write!(
+                    out,
+                    r#"<path d="M{right} {y}C{} {y} {} {back} {right} {back}H{left}C{} {back} {} {y} {left} {y}"/>"#,
+                    right + ARC / 2.0,
+                    right + ARC / 2.0,
+                    left - ARC / 2.0,
+                    left - ARC / 2.0
+                );
+            }
+            Item::Skip => {}
+        }
+    }
+}
+
+fn node_item(language: &Language, node: &Node) -> Item {
+    match node {
+        Node::NonTerm { node, .. } => Item::NonTerminal {
+            name: node.0.clone(),
+        },
+        Node::Term(term, _, _) => {
+            let desc = language
+                .lexer
+                .mapping
+                .iter()
+                .find(|(t, _, _)| t == term)
+                .map(|(_, desc, _)| desc);
+
+            let label = match desc {
+                Some(TokDesc::Token(lit)) => lit.clone(),
+                Some(TokDesc::Regex(re)) => format!("/{}/", re.strip_prefix("\\A").unwrap_or(re)),
+                None => term.0.clone(),
+            };
+
+            Item::Terminal {
+                label,
+                title: format!("\"{}\"", term.0),
+            }
+        }
+    }
+}
+
+fn sequence(items: Vec<Item>) -> Item {
+    match items.is_empty() {
+        true => Item::Skip,
+        false => Item::Sequence(items),
+    }
+}
+
+fn items(language: &Language, items: &[ast::Item]) -> Item {
+    sequence(items.iter().map(|i| symbol_item(language, i)).collect())
+}
+
+fn symbol_item(language: &Language, item: &ast::Item) -> Item {
+    match item {
+        ast::Item::Node(node) => node_item(language, node),
+        ast::Item::Group { alts, .. } => match &alts[..] {
+            [alt] => items(language, alt),
+            alts => Item::Choice(alts.iter().map(|alt| items(language, alt)).collect()),
+        },
+        ast::Item::Repeat { item, repeat, .. } => {
+            let inner = symbol_item(language, item);
+
+            match repeat {
+                Repeat::Optional => Item::Choice(vec![inner, Item::Skip]),
+                Repeat::Star => Item::Choice(vec![Item::Loop(Box::new(inner)), Item::Skip]),
+                Repeat::Plus => Item::Loop(Box::new(inner)),
+            }
+        }
+        ast::Item::Macro { name, instance, .. } => Item::NonTerminal {
+            name: instance.as_ref().unwrap_or(name).0.clone(),
+        },
+    }
+}
+
+fn rule_item(language: &Language, rule: &Rule) -> Item {
+    match &rule.items {
+        Some(written) => items(language, written),
+        None => sequence(rule.nodes.iter().map(|n| node_item(language, n)).collect()),
+    }
+}
+
+fn def_item(language: &Language, def: &NonTermDef) -> Item {
+    Item::Choice(
+        def.rules
+            .iter()
+            .map(|rule| rule_item(language, rule))
+            .collect(),
+    )
+}
+
+pub fn svg(language: &Language, def: &NonTermDef) -> String {
+    let item = def_item(language, def);
+    let width = item.width() + 2.0 * PADDING + 2.0 * H_GAP;
+    let height = item.up() + item.down() + 2.0 * PADDING;
+    let y = PADDING + item.up();
+    let start = PADDING;
+    let end = start + H_GAP + item.width() + H_GAP;
+
+    let mut out = format!(
+        r#"<svg class="railroad" xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}">"#
+    );
+
+    let _ = write!(
+        out,
+        r#"<path d="M{start} {}V{}M{start} {y}H{}"/>"#,
+        y - 8.0,
+        y + 8.0,
+        start + H_GAP
+    );
+    item.render(&mut out, start + H_GAP, y);
+    let _ = write!(
+        out,
+        r#"<path d="M{} {y}H{end}M{end} {}V{}"/>"#,
+        end - H_GAP,
+        y - 8.0,
+        y + 8.0
+    );
+
+    out.push_str("</svg>");
+    out
+}
+
+const STYLE: &str = "\
+body { font-family: sans-serif; margin: 2em; }
+h2 { font-family: monospace; }
+svg.railroad path { fill: none; stroke: #333; stroke-width: 2; }
+svg.railroad rect { stroke: #333; stroke-width: 2; }
+svg.railroad rect.terminal { fill: #dfe; }
+svg.railroad rect.nonterminal { fill: #def; }
+svg.railroad text { font-family: monospace; font-size: 14px; text-anchor: middle; }
+svg.railroad a:hover rect { fill: #bdf; }
+";
+
+pub fn html(language: &Language, title: &str) -> String {
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>\n{STYLE}</style>\n</head>\n<body>\n<h1>{}</h1>\n",
+        escape(title),
+        escape(title)
+    );
+
+    for def in language.grammar.written() {
+        let name = escape(&def.name.0);
+        let _ = writeln!(out, "<h2 id=\"{name}\">{name}</h2>");
+        out.push_str(&svg(language, def));
+        out.push('\n');
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,120 @@
+use std::{collections::BTreeSet, fmt::Write};
+
+use crate::ast::{Grammar, Node, NonTerm};
+
+fn quote(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+pub fn dot(grammar: &Grammar, name: &str) -> String {
+    let nullable = grammar.nullable();
+    let corners = grammar.left_corners();
+    let left_recursive = grammar.left_recursive();
+    let reachable = grammar.reachable();
+    let has_entry = grammar.nonterms.iter().any(|def| def.is_pub);
+
+    let defined: BTreeSet<_> = grammar.nonterms.iter().map(|def| &def.name).collect();
+    let undefined: BTreeSet<_> = grammar
+        .nonterms
+        .iter()
+        .flat_map(|def| def.rules.iter().flat_map(|rule| rule.nodes.iter()))
+        .filter_map(|node| match node {
+            Node::NonTerm { node, .. } if !defined.contains(node) => Some(node),
+            _ => None,
+        })
+        .collect();
+
+    let mut out = String::new();
+    let _ = writeln!(out, "digraph {} {{", quote(name));
+    out.push_str("\t// double border: pub, yellow: nullable, red: left-recursive,\n");
+    out.push_str("\t// grey dotted: unreachable, dashed box: undefined\n");
+    out.push_str("\tnode [shape=ellipse, fontname=\"monospace\"];\n");
+
+    for def in grammar.nonterms.iter() {
+        let mut attrs = Vec::new();
+        let mut style = Vec::new();
+        let mut notes = Vec::new();
+
+        if def.is_pub {
+            attrs.push("peripheries=2".to_owned());
+            notes.push("pub");
+        }
+
+        if nullable.contains(&def.name) {
+            style.push("filled");
+            attrs.push("fillcolor=\"lightyellow\"".to_owned());
+            notes.push("nullable");
+        }
+
+        if left_recursive.contains(&def.name) {
+            attrs.push("color=\"red\"".to_owned());
+            attrs.push("penwidth=2".to_owned());
+            notes.push("left-recursive");
+        }
+
+        if has_entry && !reachable.contains(&def.name) {
+            style.push("dotted");
+            attrs.push("fontcolor=\"grey\"".to_owned());
+            if !left_recursive.contains(&def.name) {
+                attrs.push("color=\"grey\"".to_owned());
+            }
+            notes.push("unreachable");
+        }
+
+        if !style.is_empty() {
+            attrs.push(format!("style=\"{}\"", style.join(",")));
+        }
+
+        if !notes.is_empty() {
+            attrs.push(format!("tooltip={}", quote(&notes.join(", "))));
+        }
+
+        let _ = writeln!(out, "\t{} [{}];", quote(&def.name.0), attrs.join(", "));
+    }
+
+    for name in undefined.iter() {
+        let _ = writeln!(
+            out,
+            "\t{} [shape=box, style=\"dashed\", tooltip=\"undefined\"];",
+            quote(&name.0)
+        );
+    }
+
+    let is_nullable = |name: &NonTerm| nullable.contains(name);
+
+    for def in grammar.nonterms.iter() {
+        for (i, rule) in def.rules.iter().enumerate() {
+            let mut left_corner = true;
+
+            for node in rule.nodes.iter() {
+                let Node::NonTerm { node, .. } = node else {
+                    left_corner = false;
+                    continue;
+                };
+
+                let on_cycle = left_corner
+                    && corners
+                        .get(node)
+                        .is_some_and(|corners| corners.contains(&def.name));
+                let color = if on_cycle {
+                    ", color=\"red\", penwidth=2"
+                } else {
+                    ""
+                };
+
+                let _ = writeln!(
+                    out,
+                    "\t{} -> {} [tooltip=\"rule {}\"{color}];",
+                    quote(&def.name.0),
+                    quote(&node.0),
+                    i + 1
+                );
+
+                left_corner &= is_nullable(node);
+            }
+        }
+    }
+
+    out.push_str("}\n");
+    out
+}
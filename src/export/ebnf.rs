@@ -0,0 +1,266 @@
+use std::{collections::HashMap, fmt::Write};
+
+use crate::{
+    ast::{Item, Language, Node, Repeat, Rule},
+    lexer::{Term, TokDesc},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Notation {
+    Bnf,
+    IsoEbnf,
+    W3cEbnf,
+}
+
+impl Notation {
+    fn nonterm(self, name: &str) -> String {
+        match self {
+            Notation::Bnf => format!("<{name}>"),
+            Notation::IsoEbnf | Notation::W3cEbnf => name.to_owned(),
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        match self {
+            Notation::IsoEbnf => " , ",
+            Notation::Bnf | Notation::W3cEbnf => " ",
+        }
+    }
+
+    fn definition(self) -> &'static str {
+        match self {
+            Notation::IsoEbnf => "=",
+            Notation::Bnf | Notation::W3cEbnf => "::=",
+        }
+    }
+
+    fn terminator(self) -> &'static str {
+        match self {
+            Notation::IsoEbnf => " ;",
+            Notation::Bnf | Notation::W3cEbnf => "",
+        }
+    }
+
+    fn regex(self, re: &str) -> String {
+        match self {
+            Notation::Bnf => format!("/{re}/"),
+            Notation::IsoEbnf => format!("? {re} ?"),
+            Notation::W3cEbnf => re.to_owned(),
+        }
+    }
+
+    /// None of the notations can escape quotes, so a literal containing both
+    /// kinds is split into a concatenation of pieces.
+    fn literal(self, text: &str) -> String {
+        let mut pieces = vec![String::new()];
+
+        for c in text.chars() {
+            let piece = pieces.last_mut().unwrap();
+            let closing = match c {
+                '"' => piece.contains('\''),
+                '\'' => piece.contains('"'),
+                _ => false,
+            };
+
+            if closing {
+                pieces.push(c.to_string());
+            } else {
+                piece.push(c);
+            }
+        }
+
+        let pieces: Vec<_> = pieces
+            .iter()
+            .map(|piece| match piece.contains('"') {
+                true => format!("'{piece}'"),
+                false => format!("\"{piece}\""),
+            })
+            .collect();
+
+        if pieces.len() == 1 || self == Notation::Bnf {
+            pieces.join(self.separator())
+        } else {
+            format!("( {} )", pieces.join(self.separator()))
+        }
+    }
+}
+
+struct Exporter<'l> {
+    notation: Notation,
+    tokens: HashMap<&'l Term, &'l TokDesc>,
+    names: HashMap<&'l Term, String>,
+}
+
+impl<'l> Exporter<'l> {
+    fn new(language: &'l Language, notation: Notation) -> Self {
+        Exporter {
+            notation,
+            tokens: language
+                .lexer
+                .mapping
+                .iter()
+                .map(|(term, desc, _)| (term, desc))
+                .collect(),
+            names: super::regex_names(language, &[]),
+        }
+    }
+
+    fn term(&self, term: &Term) -> String {
+        match (self.tokens.get(term), self.names.get(term)) {
+            (Some(TokDesc::Token(lit)), _) => self.notation.literal(lit),
+            (_, Some(name)) => self.notation.nonterm(name),
+            _ => self.notation.literal(&term.0),
+        }
+    }
+
+    fn node(&self, node: &Node) -> String {
+        match node {
+            Node::NonTerm { node, .. } => self.notation.nonterm(&node.0),
+            Node::Term(term, _, _) => self.term(term),
+        }
+    }
+
+    fn sequence(&self, items: &[Item]) -> String {
+        let symbols: Vec<_> = items.iter().map(|item| self.item(item)).collect();
+        symbols.join(self.notation.separator())
+    }
+
+    // ISO brackets group by themselves, so a repeated group loses its parens.
+    fn bracketed(&self, item: &Item) -> String {
+        match item {
+            Item::Group { alts, .. } => {
+                let alts: Vec<_> = alts.iter().map(|alt| self.sequence(alt)).collect();
+                alts.join(" | ")
+            }
+            item => self.item(item),
+        }
+    }
+
+    fn item(&self, item: &Item) -> String {
+        match item {
+            Item::Node(node) => self.node(node),
+            Item::Group { .. } => format!("( {} )", self.bracketed(item)),
+            Item::Repeat { item, repeat, .. } => match (self.notation, repeat) {
+                (Notation::IsoEbnf, Repeat::Optional) => format!("[ {} ]", self.bracketed(item)),
+                (Notation::IsoEbnf, Repeat::Star) => format!("{{ {} }}", self.bracketed(item)),
+                (Notation::IsoEbnf, Repeat::Plus) => {
+                    format!("{} , {{ {} }}", self.item(item), self.bracketed(item))
+                }
+                (_, repeat) => format!("{}{}", self.item(item), repeat.suffix()),
+            },
+            Item::Macro { name, instance, .. } => {
+                self.notation.nonterm(&instance.as_ref().unwrap_or(name).0)
+            }
+        }
+    }
+
+    // Plain BNF has no repetition or grouping, so it keeps the desugared rules.
+    fn rule(&self, rule: &Rule) -> String {
+        match (&rule.items, self.notation) {
+            (Some(items), Notation::IsoEbnf | Notation::W3cEbnf) => self.sequence(items),
+            (_, Notation::Bnf) if rule.nodes.is_empty() => "\"\"".to_owned(),
+            _ => {
+                let symbols: Vec<_> = rule.nodes.iter().map(|node| self.node(node)).collect();
+                symbols.join(self.notation.separator())
+            }
+        }
+    }
+
+    fn production(&self, out: &mut String, name: &str, alternatives: &[String]) {
+        let head = format!(
+            "{} {} ",
+            self.notation.nonterm(name),
+            self.notation.definition()
+        );
+        let indent = " ".repeat(head.chars().count() - 2);
+
+        let lines: Vec<_> = alternatives
+            .iter()
+            .enumerate()
+            .map(|(i, alternative)| match i {
+                0 => format!("{head}{alternative}"),
+                _ => format!("{indent}| {alternative}"),
+            })
+            .map(|line| line.trim_end().to_owned())
+            .collect();
+
+        let _ = writeln!(out, "{}{}", lines.join("\n"), self.notation.terminator());
+    }
+}
+
+pub fn export(language: &Language, notation: Notation) -> String {
+    let exporter = Exporter::new(language, notation);
+    let mut out = String::new();
+
+    let defs = match notation {
+        Notation::Bnf => language.grammar.nonterms.iter().collect(),
+        Notation::IsoEbnf | Notation::W3cEbnf => language.grammar.written(),
+    };
+    let empty = |rule: &Rule| rule.items.is_none() && rule.nodes.is_empty();
+
+    for def in defs {
+        let rules: Vec<_> = def.rules.iter().map(|rule| exporter.rule(rule)).collect();
+
+        let alternatives = match notation {
+            Notation::W3cEbnf if def.rules.iter().any(empty) => {
+                let rest: Vec<_> = def
+                    .rules
+                    .iter()
+                    .zip(rules)
+                    .filter(|(rule, _)| !empty(rule))
+                    .map(|(_, text)| text)
+                    .collect();
+
+                match &rest[..] {
+                    [] => vec![String::new()],
+                    _ => vec![format!("( {} )?", rest.join(" | "))],
+                }
+            }
+            _ => rules,
+        };
+
+        exporter.production(&mut out, &def.name.0, &alternatives);
+    }
+
+    let mut regexes = language
+        .lexer
+        .mapping
+        .iter()
+        .filter_map(|(term, desc, _)| match desc {
+            TokDesc::Regex(re) => Some((term, re.strip_prefix("\\A").unwrap_or(re))),
+            TokDesc::Token(_) => None,
+        })
+        .peekable();
+
+    if regexes.peek().is_some() {
+        out.push('\n');
+    }
+
+    for (term, re) in regexes {
+        exporter.production(&mut out, &exporter.names[term], &[notation.regex(re)]);
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Notation;
+
+    #[test]
+    fn literal_quotes() {
+        assert_eq!(Notation::IsoEbnf.literal("a"), r#""a""#);
+        assert_eq!(Notation::IsoEbnf.literal(r#""a"#), r#"'"a'"#);
+        assert_eq!(Notation::IsoEbnf.literal("'a"), r#""'a""#);
+    }
+
+    #[test]
+    fn literal_with_both_quotes() {
+        assert_eq!(
+            Notation::IsoEbnf.literal(r#"a"b'c"d"#),
+            r#"( 'a"b' , "'c" , '"d' )"#
+        );
+        assert_eq!(Notation::Bnf.literal(r#""'"#), r#"'"' "'""#);
+        assert_eq!(Notation::W3cEbnf.literal(r#"'""#), r#"( "'" '"' )"#);
+    }
+}
@@ -0,0 +1,483 @@
+use std::{collections::HashSet, fmt::Write};
+
+use crate::{
+    ast::{Item, Language, Node, NonTermDef},
+    lexer::TokDesc,
+    notation::format::code_lines,
+};
+
+use super::Warning;
+
+fn string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn raw_string(text: &str) -> String {
+    let mut hashes = String::new();
+
+    while text.contains(&format!("\"{hashes}")) {
+        hashes.push('#');
+    }
+
+    format!("r{hashes}\"{text}\"{hashes}")
+}
+
+fn preamble(language: &Language, warnings: &mut Vec<Warning>) -> (Vec<String>, Vec<String>) {
+    let mut uses = Vec::new();
+    let mut rest = Vec::new();
+    let mut depth = 0usize;
+    let mut item = String::new();
+
+    for ch in language.preamble.chars() {
+        item.push(ch);
+
+        match ch {
+            '{' => depth += 1,
+            '}' => depth = depth.saturating_sub(1),
+            ';' if depth == 0 => {
+                let text = item.trim().to_owned();
+
+                if text.starts_with("use ") || text.starts_with("pub use ") {
+                    uses.push(text);
+                } else {
+                    rest.push(text);
+                }
+
+                item.clear();
+            }
+            _ => {}
+        }
+    }
+
+    if !item.trim().is_empty() {
+        rest.push(item.trim().to_owned());
+    }
+
+    if !rest.is_empty() {
+        warnings.push(Warning {
+            span: None,
+            message: "LALRPOP only accepts `use` items, the rest of the preamble was commented out"
+                .to_owned(),
+        });
+    }
+
+    (uses, rest)
+}
+
+struct Exporter {
+    names: HashSet<String>,
+    lifted: Vec<String>,
+    warnings: Vec<Warning>,
+}
+
+impl Exporter {
+    fn node(&mut self, node: &Node, nested: bool) -> String {
+        let (symbol, name) = match node {
+            Node::NonTerm {
+                node,
+                extract_name,
+                args,
+                span,
+            } => {
+                if let Some(args) = args {
+                    self.warnings.push(Warning {
+                        span: Some(*span),
+                        message: format!(
+                            "argument `{}` to {} has no LALRPOP equivalent and was dropped",
+                            args.trim(),
+                            node.0
+                        ),
+                    });
+                }
+
+                (node.0.clone(), extract_name)
+            }
+            Node::Term(term, name, _) => (string(&term.0), name),
+        };
+
+        select(symbol, name.as_ref(), nested)
+    }
+
+    // LALRPOP parentheses only hold a sequence, so a group with several
+    // alternatives becomes a nonterminal of its own. Its type is inferred
+    // when the value is used and `()` otherwise, since the alternatives of an
+    // unbound group may disagree.
+    fn lift(&mut self, owner: &str, params: &[String], alts: &[Vec<Item>], bound: bool) -> String {
+        let mut name = format!("{owner}_group");
+        let mut n = 1;
+
+        while !self.names.insert(name.clone()) {
+            n += 1;
+            name = format!("{owner}_group_{n}");
+        }
+
+        if !params.is_empty() {
+            name = format!("{name}<{}>", params.join(", "));
+        }
+
+        let bound = bound && alts.iter().all(|alt| !alt.is_empty());
+        let mut out = match bound {
+            true => format!("{name} = {{\n"),
+            false => format!("{name}: () = {{\n"),
+        };
+
+        for alt in alts.iter() {
+            let symbols = self.sequence(owner, params, alt, true, bound);
+
+            let _ = match (bound, symbols.is_empty()) {
+                (true, _) => writeln!(out, "    {symbols},"),
+                (false, true) => writeln!(out, "    => (),"),
+                (false, false) => writeln!(out, "    {symbols} => (),"),
+            };
+        }
+
+        out.push_str("};\n");
+        self.lifted.push(out);
+        name
+    }
+
+    fn sequence(
+        &mut self,
+        owner: &str,
+        params: &[String],
+        items: &[Item],
+        nested: bool,
+        bound: bool,
+    ) -> String {
+        let named = items.iter().any(|item| item.extract_name().is_some());
+
+        items
+            .iter()
+            .map(|item| {
+                let used = bound && (!named || item.extract_name().is_some());
+                self.symbol(owner, params, item, nested, used)
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn symbol(
+        &mut self,
+        owner: &str,
+        params: &[String],
+        item: &Item,
+        nested: bool,
+        bound: bool,
+    ) -> String {
+        let bound = bound || (!nested && item.extract_name().is_some());
+        let text = match item {
+            Item::Node(n) => return self.node(n, nested),
+            Item::Group { alts, .. } => match &alts[..] {
+                [alt] => format!("({})", self.sequence(owner, params, alt, true, bound)),
+                alts => self.lift(owner, params, alts, bound),
+            },
+            Item::Repeat {
+                item: inner,
+                repeat,
+                ..
+            } => {
+                let text = self.symbol(owner, params, inner, true, bound);
+
+                match &**inner {
+                    Item::Repeat { .. } => format!("({text}){}", repeat.suffix()),
+                    _ => format!("{text}{}", repeat.suffix()),
+                }
+            }
+            Item::Macro { name, args, .. } => {
+                let args: Vec<_> = args
+                    .iter()
+                    .map(|arg| self.symbol(owner, params, arg, true, bound))
+                    .collect();
+                format!("{}<{}>", name.0, args.join(", "))
+            }
+        };
+
+        select(text, item.extract_name(), nested)
+    }
+
+    fn def(&mut self, out: &mut String, def: &NonTermDef, params: &[String]) {
+        if let Some(args) = &def.args {
+            let args = args.trim();
+
+            self.warnings.push(Warning {
+                span: Some(def.name_span),
+                message: format!(
+                    "{} takes arguments `{args}`, which LALRPOP has no equivalent for; they were dropped",
+                    def.name.0
+                ),
+            });
+            let _ = writeln!(out, "// FIXME: l1l arguments `{args}` were dropped");
+        }
+
+        let vis = if def.is_pub { "pub " } else { "" };
+        let name = match params {
+            [] => def.name.0.clone(),
+            params => format!("{}<{}>", def.name.0, params.join(", ")),
+        };
+        let _ = writeln!(out, "{vis}{name}: {} = {{", def.ret_ty.trim());
+
+        for rule in def.rules.iter() {
+            let mut line = String::from("    ");
+
+            match &rule.items {
+                Some(items) => {
+                    for i in items.iter() {
+                        line.push_str(&self.symbol(&def.name.0, params, i, false, false));
+                        line.push(' ');
+                    }
+                }
+                None => {
+                    for n in rule.nodes.iter() {
+                        line.push_str(&self.node(n, false));
+                        line.push(' ');
+                    }
+                }
+            }
+
+            let _ = writeln!(out, "{line}=> {},", action(&rule.code));
+        }
+
+        out.push_str("};\n");
+
+        for lifted in std::mem::take(&mut self.lifted) {
+            out.push('\n');
+            out.push_str(&lifted);
+        }
+    }
+}
+
+// Named symbols are only allowed at the top level of an alternative; inside
+// parentheses and macro arguments a binding becomes a plain `<T>` selection.
+fn select(text: String, name: Option<&String>, nested: bool) -> String {
+    match (name, nested) {
+        (Some(name), false) => format!("<{name}:{text}>"),
+        (Some(_), true) => format!("<{text}>"),
+        (None, _) => text,
+    }
+}
+
+fn action(code: &str) -> String {
+    match &code_lines(code)[..] {
+        [] => "()".to_owned(),
+        [line] if !line.contains(';') => line.clone(),
+        [line] => format!("{{ {line} }}"),
+        lines => {
+            let mut out = "{\n".to_owned();
+
+            for line in lines {
+                if !line.is_empty() {
+                    out.push_str("        ");
+                    out.push_str(line);
+                }
+
+                out.push('\n');
+            }
+
+            out.push_str("    }");
+            out
+        }
+    }
+}
+
+pub fn export(language: &Language) -> (String, Vec<Warning>) {
+    let mut exporter = Exporter {
+        names: language
+            .grammar
+            .nonterms
+            .iter()
+            .filter(|def| def.helper.is_none())
+            .map(|def| def.name.0.clone())
+            .chain(language.grammar.macros.iter().map(|m| m.def.name.0.clone()))
+            .collect(),
+        lifted: Vec::new(),
+        warnings: Vec::new(),
+    };
+    let mut out = String::new();
+    let (uses, rest) = preamble(language, &mut exporter.warnings);
+
+    for item in uses.iter() {
+        let _ = writeln!(out, "{item}");
+    }
+
+    for item in rest.iter() {
+        for line in item.lines() {
+            let _ = writeln!(out, "// {line}");
+        }
+    }
+
+    if !uses.is_empty() || !rest.is_empty() {
+        out.push('\n');
+    }
+
+    out.push_str("grammar;\n\nmatch {\n");
+
+    for (term, desc, _) in language.lexer.mapping.iter() {
+        let _ = match desc {
+            TokDesc::Token(lit) if *lit == term.0 => writeln!(out, "    {},", string(lit)),
+            TokDesc::Token(lit) => writeln!(out, "    {} => {},", string(lit), string(&term.0)),
+            TokDesc::Regex(re) => writeln!(
+                out,
+                "    {} => {},",
+                raw_string(re.strip_prefix("\\A").unwrap_or(re)),
+                string(&term.0)
+            ),
+        };
+    }
+
+    out.push_str("}\n");
+
+    for nonterm in language
+        .grammar
+        .nonterms
+        .iter()
+        .filter(|def| def.helper.is_none())
+    {
+        out.push('\n');
+        exporter.def(&mut out, nonterm, &[]);
+    }
+
+    for m in language.grammar.macros.iter() {
+        out.push('\n');
+        exporter.def(&mut out, &m.def, &m.params);
+    }
+
+    (out, exporter.warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs};
+
+    use super::export;
+    use crate::parse_language;
+
+    fn process(name: &str, source: &str) {
+        let dir = env::temp_dir().join(format!("l1l-lalrpop-{}-{name}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let (grammar, _) = export(&parse_language(source).unwrap());
+        let path = dir.join(format!("{name}.lalrpop"));
+        fs::write(&path, grammar).unwrap();
+
+        let result = lalrpop::Configuration::new()
+            .set_in_dir(&dir)
+            .set_out_dir(&dir)
+            .force_build(true)
+            .log_quiet()
+            .process_file(&path);
+        let generated = dir.join(format!("{name}.rs")).exists();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_ok() && generated, "{result:?}");
+    }
+
+    #[test]
+    fn comments_out_what_lalrpop_lacks() {
+        let language = parse_language(
+            r#"
+preamble = !{ use std::fmt; const N: usize = 1; }!
+
+tokens = {
+    "id" = regex "[a-z_][a-z0-9_]*",
+    "lp" = token "(",
+    "rp" = token ")",
+    "," = token ",",
+}
+
+rules = {
+    pub call: !{ usize }! = {
+        "id" "lp" <n:args(!{ 0 }!)> "rp" => !{ n }!,
+    }
+
+    args: !{ count: usize }! -> !{ usize }! = {
+        "id" ("," "id")* => !{ count + 1 }!,
+        => !{ count }!,
+    }
+}
+"#,
+        )
+        .unwrap();
+        let (grammar, warnings) = export(&language);
+
+        assert_eq!(
+            grammar,
+            r#"use std::fmt;
+// const N: usize = 1;
+
+grammar;
+
+match {
+    r"[a-z_][a-z0-9_]*" => "id",
+    "(" => "lp",
+    ")" => "rp",
+    ",",
+}
+
+pub call: usize = {
+    "id" "lp" <n:args> "rp" => n,
+};
+
+// FIXME: l1l arguments `count: usize` were dropped
+args: usize = {
+    "id" ("," "id")* => count + 1,
+    => count,
+};
+"#
+        );
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| &warning.message[..])
+                .collect::<Vec<_>>(),
+            [
+                "LALRPOP only accepts `use` items, the rest of the preamble was commented out",
+                "argument `0` to args has no LALRPOP equivalent and was dropped",
+                "args takes arguments `count: usize`, which LALRPOP has no equivalent for; they were dropped",
+            ]
+        );
+    }
+
+    #[test]
+    fn calculator_passes_lalrpop() {
+        process("calc", include_str!("../../examples/calculator/calc.l1l"));
+    }
+
+    #[test]
+    fn kotlin_passes_lalrpop() {
+        process("kotlin", include_str!("../../examples/kotlin/kotlin.l1l"));
+    }
+
+    #[test]
+    fn lifted_groups_and_macros_pass_lalrpop() {
+        process(
+            "lifted",
+            r#"
+preamble = !{ }!
+
+tokens = {
+    "lb" = token "[",
+    "rb" = token "]",
+    "," = token ",",
+    "a" = token "a",
+    "b" = token "b",
+    "x" = token "x",
+    "n" = regex "[0-9]+",
+}
+
+rules = {
+    Sep<T>: !{ Vec<T> }! = {
+        <first:T> <rest:("," <v:T>)*> => !{ let mut v = vec![first]; v.extend(rest); v }!,
+    }
+
+    pub list: !{ Vec<&'input str> }! = {
+        "lb" <items:Sep<item>> "rb" => !{ items }!,
+    }
+
+    item: !{ &'input str }! = {
+        <v:("a" | "b")> ("x" "n" | list)? => !{ v }!,
+    }
+}
+"#,
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Language, Span},
+    lexer::{Term, TokDesc},
+};
+
+pub mod diagram;
+pub mod dot;
+pub mod ebnf;
+pub mod lalrpop;
+pub mod pest;
+
+#[derive(Debug, Clone)]
+pub struct Warning {
+    pub span: Option<Span>,
+    pub message: String,
+}
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn regex_names<'l>(language: &'l Language, reserved: &[&str]) -> HashMap<&'l Term, String> {
+    let nonterms: Vec<_> = language
+        .grammar
+        .nonterms
+        .iter()
+        .map(|def| &def.name.0[..])
+        .collect();
+
+    language
+        .lexer
+        .mapping
+        .iter()
+        .filter(|(_, desc, _)| matches!(desc, TokDesc::Regex(_)))
+        .map(|(term, _, _)| {
+            let mut name: String = term
+                .0
+                .chars()
+                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                .collect();
+
+            if nonterms.contains(&&name[..]) || reserved.contains(&&name[..]) {
+                name.push_str("_token");
+            }
+
+            (term, name)
+        })
+        .collect()
+}
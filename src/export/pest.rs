@@ -0,0 +1,411 @@
+use std::{collections::HashMap, fmt::Write};
+
+use regex_syntax::hir::{Class, ClassUnicode, Hir, HirKind, Look};
+
+use crate::{
+    ast::{Item, Language, Node, Rule, Span},
+    lexer::{Term, TokDesc},
+};
+
+use super::Warning;
+
+const BUILTINS: &[&str] = &[
+    "ANY",
+    "SOI",
+    "EOI",
+    "WHITESPACE",
+    "COMMENT",
+    "NEWLINE",
+    "PUSH",
+    "POP",
+    "POP_ALL",
+    "PEEK",
+    "PEEK_ALL",
+    "DROP",
+    "ASCII",
+    "ASCII_DIGIT",
+    "ASCII_ALPHA",
+    "ASCII_ALPHANUMERIC",
+];
+
+fn escape(ch: char, quote: char) -> String {
+    match ch {
+        '\\' => "\\\\".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\t' => "\\t".to_owned(),
+        ch if ch == quote => format!("\\{ch}"),
+        ch if ch == ' ' || ch.is_ascii_graphic() => ch.to_string(),
+        ch => format!("\\u{{{:X}}}", ch as u32),
+    }
+}
+
+fn string(text: &str) -> String {
+    format!(
+        "\"{}\"",
+        text.chars().map(|ch| escape(ch, '"')).collect::<String>()
+    )
+}
+
+fn char_literal(ch: char) -> String {
+    format!("'{}'", escape(ch, '\''))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Prec {
+    Alt,
+    Seq,
+    Atom,
+}
+
+fn wrap((expr, prec): (String, Prec), min: Prec) -> String {
+    if prec < min {
+        format!("({expr})")
+    } else {
+        expr
+    }
+}
+
+fn ranges(class: &ClassUnicode) -> (String, Prec) {
+    let ranges: Vec<_> = class
+        .ranges()
+        .iter()
+        .map(|range| match (range.start(), range.end()) {
+            (start, end) if start == end => char_literal(start),
+            (start, end) => format!("{}..{}", char_literal(start), char_literal(end)),
+        })
+        .collect();
+
+    match &ranges[..] {
+        [one] => (one.clone(), Prec::Atom),
+        ranges => (ranges.join(" | "), Prec::Alt),
+    }
+}
+
+fn class(class: &ClassUnicode) -> (String, Prec) {
+    let mut negated = class.clone();
+    negated.negate();
+
+    if negated.ranges().is_empty() {
+        ("ANY".to_owned(), Prec::Atom)
+    } else if negated.ranges().len() < class.ranges().len() {
+        let inner = wrap(ranges(&negated), Prec::Atom);
+        (format!("!{inner} ~ ANY"), Prec::Seq)
+    } else {
+        ranges(class)
+    }
+}
+
+fn regex(hir: &Hir) -> Result<(String, Prec), String> {
+    let expr = match hir.kind() {
+        HirKind::Empty => ("\"\"".to_owned(), Prec::Atom),
+        HirKind::Literal(lit) => match std::str::from_utf8(&lit.0) {
+            Ok(text) => (string(text), Prec::Atom),
+            Err(_) => return Err("byte literals have no pest equivalent".to_owned()),
+        },
+        HirKind::Class(Class::Unicode(cls)) => class(cls),
+        HirKind::Class(Class::Bytes(_)) => {
+            return Err("byte classes have no pest equivalent".to_owned())
+        }
+        HirKind::Look(Look::Start | Look::StartLF | Look::StartCRLF) => {
+            ("SOI".to_owned(), Prec::Atom)
+        }
+        HirKind::Look(Look::End | Look::EndLF | Look::EndCRLF) => ("EOI".to_owned(), Prec::Atom),
+        HirKind::Look(_) => return Err("word boundaries have no pest equivalent".to_owned()),
+        HirKind::Repetition(rep) => {
+            let inner = wrap(regex(&rep.sub)?, Prec::Atom);
+            let suffix = match (rep.min, rep.max) {
+                (0, None) => "*".to_owned(),
+                (1, None) => "+".to_owned(),
+                (0, Some(1)) => "?".to_owned(),
+                (min, None) => format!("{{{min},}}"),
+                (min, Some(max)) if min == max => format!("{{{min}}}"),
+                (min, Some(max)) => format!("{{{min}, {max}}}"),
+            };
+
+            (format!("{inner}{suffix}"), Prec::Atom)
+        }
+        HirKind::Capture(capture) => regex(&capture.sub)?,
+        HirKind::Concat(hirs) => {
+            let parts = hirs
+                .iter()
+                .map(|hir| regex(hir).map(|expr| wrap(expr, Prec::Seq)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            (parts.join(" ~ "), Prec::Seq)
+        }
+        HirKind::Alternation(hirs) => {
+            let parts = hirs
+                .iter()
+                .map(|hir| regex(hir).map(|expr| wrap(expr, Prec::Alt)))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            (parts.join(" | "), Prec::Alt)
+        }
+    };
+
+    Ok(expr)
+}
+
+fn sequence(parts: Vec<(String, Prec)>) -> (String, Prec) {
+    match &parts[..] {
+        [] => ("\"\"".to_owned(), Prec::Atom),
+        [_] => parts.into_iter().next().unwrap(),
+        parts => {
+            let parts: Vec<_> = parts
+                .iter()
+                .map(|part| wrap(part.clone(), Prec::Seq))
+                .collect();
+
+            (parts.join(" ~ "), Prec::Seq)
+        }
+    }
+}
+
+// pest tries alternatives in order, so an empty one becomes an optional
+// around the rest instead of a `""` that would always match first.
+fn choice(alternatives: Vec<Vec<(String, Prec)>>) -> (String, Prec) {
+    let nullable = alternatives.iter().any(Vec::is_empty);
+    let alternatives: Vec<_> = alternatives
+        .into_iter()
+        .filter(|parts| !parts.is_empty())
+        .map(sequence)
+        .collect();
+
+    let joined = alternatives
+        .iter()
+        .map(|(text, _)| &text[..])
+        .collect::<Vec<_>>()
+        .join(" | ");
+
+    match (&alternatives[..], nullable) {
+        ([], _) => ("\"\"".to_owned(), Prec::Atom),
+        ([one], true) => (format!("{}?", wrap(one.clone(), Prec::Atom)), Prec::Atom),
+        (_, true) => (format!("({joined})?"), Prec::Atom),
+        ([one], false) => one.clone(),
+        (_, false) => (joined, Prec::Alt),
+    }
+}
+
+struct Exporter<'l> {
+    tokens: HashMap<&'l Term, &'l TokDesc>,
+    names: HashMap<&'l Term, String>,
+}
+
+impl<'l> Exporter<'l> {
+    fn node(&self, node: &Node) -> String {
+        match node {
+            Node::NonTerm { node, .. } => node.0.clone(),
+            Node::Term(term, _, _) => match (self.tokens.get(term), self.names.get(term)) {
+                (Some(TokDesc::Token(lit)), _) => string(lit),
+                (_, Some(name)) => name.clone(),
+                _ => string(&term.0),
+            },
+        }
+    }
+
+    fn item(&self, item: &Item) -> (String, Prec) {
+        match item {
+            Item::Node(node) => (self.node(node), Prec::Atom),
+            Item::Group { alts, .. } => choice(
+                alts.iter()
+                    .map(|alt| alt.iter().map(|item| self.item(item)).collect())
+                    .collect(),
+            ),
+            Item::Repeat { item, repeat, .. } => (
+                format!("{}{}", wrap(self.item(item), Prec::Atom), repeat.suffix()),
+                Prec::Atom,
+            ),
+            Item::Macro { name, instance, .. } => {
+                (instance.as_ref().unwrap_or(name).0.clone(), Prec::Atom)
+            }
+        }
+    }
+
+    fn rule(&self, rule: &Rule) -> Vec<(String, Prec)> {
+        match &rule.items {
+            Some(items) => items.iter().map(|item| self.item(item)).collect(),
+            None => rule
+                .nodes
+                .iter()
+                .map(|node| (self.node(node), Prec::Atom))
+                .collect(),
+        }
+    }
+}
+
+fn warning(span: Span, message: String) -> Warning {
+    Warning {
+        span: Some(span),
+        message,
+    }
+}
+
+pub fn export(language: &Language) -> (String, Vec<Warning>) {
+    let exporter = Exporter {
+        tokens: language
+            .lexer
+            .mapping
+            .iter()
+            .map(|(term, desc, _)| (term, desc))
+            .collect(),
+        names: super::regex_names(language, BUILTINS)
+            .into_iter()
+            .map(
+                |(term, name)| match name.starts_with(|ch: char| ch.is_ascii_digit()) {
+                    true => (term, format!("t{name}")),
+                    false => (term, name),
+                },
+            )
+            .collect(),
+    };
+
+    let mut warnings = Vec::new();
+    let mut out = String::from("WHITESPACE = _{ WHITE_SPACE }\n");
+
+    for def in language.grammar.written() {
+        if BUILTINS.contains(&&def.name.0[..]) {
+            warnings.push(warning(
+                def.name_span,
+                format!("{} is a built-in pest rule, rename it", def.name.0),
+            ));
+        }
+
+        let (body, _) = choice(def.rules.iter().map(|rule| exporter.rule(rule)).collect());
+
+        let _ = write!(out, "\n{} = {{ {body} }}\n", def.name.0);
+    }
+
+    let mut regexes = language
+        .lexer
+        .mapping
+        .iter()
+        .filter_map(|(term, desc, span)| match desc {
+            TokDesc::Regex(re) => Some((term, re.strip_prefix("\\A").unwrap_or(re), span)),
+            TokDesc::Token(_) => None,
+        })
+        .peekable();
+
+    if regexes.peek().is_some() {
+        out.push('\n');
+    }
+
+    for (term, re, span) in regexes {
+        let parsed = regex_syntax::parse(re).map_err(|e| e.to_string());
+        let body = match parsed.and_then(|hir| regex(&hir)) {
+            Ok((expr, _)) => expr,
+            Err(message) => {
+                warnings.push(warning(
+                    *span,
+                    format!("regex of \"{}\" cannot be converted: {message}", term.0),
+                ));
+                format!("\"\" /* {re} */")
+            }
+        };
+
+        let _ = writeln!(out, "{} = @{{ {body} }}", exporter.names[term]);
+    }
+
+    (out, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::export;
+    use crate::parse_language;
+
+    fn pest(source: &str) -> String {
+        let (grammar, warnings) = export(&parse_language(source).unwrap());
+        assert!(warnings.is_empty());
+        grammar
+    }
+
+    #[test]
+    fn exports_the_calculator() {
+        assert_eq!(
+            pest(include_str!("../../examples/calculator/calc.l1l")),
+            r#"WHITESPACE = _{ WHITE_SPACE }
+
+atom = { "(" ~ expr ~ ")" | n }
+
+prod = { atom ~ (("*" | "/") ~ atom)* }
+
+expr = { prod ~ (("+" | "-") ~ prod)* }
+
+n = @{ '0'..'9'+ }
+"#
+        );
+    }
+
+    #[test]
+    fn puts_empty_alternatives_last() {
+        let grammar = pest(
+            r#"
+preamble = !{ use std::fmt; const N: usize = 1; }!
+
+tokens = {
+    "id" = regex "[a-z_][a-z0-9_]*",
+    "lp" = token "(",
+    "rp" = token ")",
+    "," = token ",",
+}
+
+rules = {
+    pub call: !{ usize }! = {
+        "id" "lp" <n:args(!{ 0 }!)> "rp" => !{ n }!,
+    }
+
+    args: !{ count: usize }! -> !{ usize }! = {
+        "id" ("," "id")* => !{ count + 1 }!,
+        => !{ count }!,
+    }
+}
+"#,
+        );
+
+        assert_eq!(
+            grammar,
+            r#"WHITESPACE = _{ WHITE_SPACE }
+
+call = { id ~ "(" ~ args ~ ")" }
+
+args = { (id ~ ("," ~ id)*)? }
+
+id = @{ ('_' | 'a'..'z') ~ ('0'..'9' | '_' | 'a'..'z')* }
+"#
+        );
+    }
+
+    #[test]
+    fn warns_about_builtins_and_word_boundaries() {
+        let (_, warnings) = export(
+            &parse_language(
+                r#"
+preamble = !{ }!
+
+tokens = {
+    "b" = regex "\bx",
+}
+
+rules = {
+    pub EOI: !{ () }! = {
+        "b" => !{ () }!,
+    }
+}
+"#,
+            )
+            .unwrap(),
+        );
+
+        assert_eq!(
+            warnings
+                .iter()
+                .map(|warning| &warning.message[..])
+                .collect::<Vec<_>>(),
+            [
+                "EOI is a built-in pest rule, rename it",
+                "regex of \"b\" cannot be converted: word boundaries have no pest equivalent",
+            ]
+        );
+    }
+}
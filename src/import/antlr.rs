@@ -0,0 +1,1254 @@
+use std::collections::{HashMap, HashSet};
+
+use regex::Regex;
+
+use super::{lower, Action, Alt, Def, Elem, Import, Issue, Repeat, Source, Sym};
+use crate::sample::{regex::generate, Rng};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Tok {
+    Ident(String),
+    Literal(String),
+    Set(String),
+    Action(String),
+    Punct(&'static str),
+}
+
+const PUNCTS: &[&str] = &[
+    "..", "->", "+=", "::", ":", ";", "|", "(", ")", "?", "*", "+", "~", ".", ",", "=", "#", "<",
+    ">", "@",
+];
+
+type Result<T> = std::result::Result<T, Issue>;
+
+const LONGEST_MATCH_SAMPLES: u64 = 32;
+
+fn escaped(chars: &mut std::str::Chars) -> Option<char> {
+    let ch = match chars.next()? {
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'b' => '\u{8}',
+        'f' => '\u{c}',
+        'u' => {
+            let rest = chars.as_str();
+            let (hex, len) = match rest.strip_prefix('{') {
+                Some(braced) => {
+                    let end = braced.find('}').unwrap_or(braced.len());
+                    (&braced[..end], end + 2)
+                }
+                None => (rest.get(..4).unwrap_or(rest), 4),
+            };
+
+            match u32::from_str_radix(hex, 16).ok().and_then(char::from_u32) {
+                Some(ch) => {
+                    *chars = rest.get(len..).unwrap_or_default().chars();
+                    ch
+                }
+                None => 'u',
+            }
+        }
+        ch => ch,
+    };
+
+    Some(ch)
+}
+
+fn unescape(raw: &str) -> String {
+    let mut out = String::new();
+    let mut chars = raw.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => out.extend(escaped(&mut chars)),
+            ch => out.push(ch),
+        }
+    }
+
+    out
+}
+
+fn tokenize(input: &str) -> Result<Vec<(usize, Tok)>> {
+    let mut toks = Vec::new();
+    let mut pos = 0;
+
+    let error = |offset, message: &str| Issue {
+        offset,
+        message: message.to_owned(),
+    };
+
+    let closing = |from: usize, close: char| {
+        let mut escaped = false;
+
+        input[from..].char_indices().find_map(|(i, ch)| {
+            let found = !escaped && ch == close;
+            escaped = !escaped && ch == '\\';
+            found.then_some(from + i)
+        })
+    };
+
+    while let Some(ch) = input[pos..].chars().next() {
+        let rest = &input[pos..];
+        let start = pos;
+
+        if ch.is_whitespace() {
+            pos += ch.len_utf8();
+        } else if rest.starts_with("//") {
+            pos += rest.find('\n').unwrap_or(rest.len());
+        } else if let Some(comment) = rest.strip_prefix("/*") {
+            let end = comment
+                .find("*/")
+                .ok_or_else(|| error(start, "unterminated comment"))?;
+            pos += end + 4;
+        } else if ch == '_' || ch.is_ascii_alphabetic() {
+            let len = rest
+                .find(|ch: char| ch != '_' && !ch.is_ascii_alphanumeric())
+                .unwrap_or(rest.len());
+            toks.push((start, Tok::Ident(rest[..len].to_owned())));
+            pos += len;
+        } else if ch == '\'' {
+            let end = closing(pos + 1, '\'').ok_or_else(|| error(start, "unterminated literal"))?;
+            toks.push((start, Tok::Literal(unescape(&input[pos + 1..end]))));
+            pos = end + 1;
+        } else if ch == '[' {
+            let end = closing(pos + 1, ']').ok_or_else(|| error(start, "unterminated set"))?;
+            toks.push((start, Tok::Set(input[pos + 1..end].to_owned())));
+            pos = end + 1;
+        } else if ch == '{' {
+            let mut depth = 0usize;
+            let mut i = pos;
+
+            let end = loop {
+                let ch = input[i..]
+                    .chars()
+                    .next()
+                    .ok_or_else(|| error(start, "unterminated action"))?;
+
+                match ch {
+                    '{' => depth += 1,
+                    '}' if depth == 1 => break i,
+                    '}' => depth -= 1,
+                    '"' | '\'' => {
+                        i = closing(i + 1, ch)
+                            .ok_or_else(|| error(start, "unterminated action"))?;
+                    }
+                    _ => {}
+                }
+
+                i += ch.len_utf8();
+            };
+
+            toks.push((start, Tok::Action(input[start + 1..end].to_owned())));
+            pos = end + 1;
+        } else {
+            let punct = PUNCTS
+                .iter()
+                .find(|punct| rest.starts_with(*punct))
+                .ok_or_else(|| error(start, &format!("unexpected character {ch:?}")))?;
+            toks.push((start, Tok::Punct(punct)));
+            pos += punct.len();
+        }
+    }
+
+    Ok(toks)
+}
+
+fn regex_char(ch: char, class: bool) -> String {
+    match ch {
+        '\\' => "\\x5C".to_owned(),
+        '"' => "\\x22".to_owned(),
+        '\n' => "\\n".to_owned(),
+        '\r' => "\\r".to_owned(),
+        '\t' => "\\t".to_owned(),
+        ch if ch.is_control() => format!("\\x{{{:X}}}", ch as u32),
+        ch if class && "[]^-&~".contains(ch) => format!("\\{ch}"),
+        ch if class => ch.to_string(),
+        ch => regex::escape(&ch.to_string()),
+    }
+}
+
+enum ClassItem {
+    Char(char),
+    Property(String),
+    Dash,
+}
+
+fn class(raw: &str) -> String {
+    let mut items = Vec::new();
+    let mut chars = raw.chars();
+
+    while let Some(ch) = chars.next() {
+        let item = match ch {
+            '\\' if chars.as_str().starts_with(['p', 'P']) => {
+                let rest = chars.as_str();
+                let len = rest.find('}').map_or(rest.len(), |end| end + 1);
+                chars = rest[len..].chars();
+                ClassItem::Property(format!("\\{}", &rest[..len]))
+            }
+            '\\' => match escaped(&mut chars) {
+                Some(ch) => ClassItem::Char(ch),
+                None => ClassItem::Char('\\'),
+            },
+            '-' => ClassItem::Dash,
+            ch => ClassItem::Char(ch),
+        };
+
+        items.push(item);
+    }
+
+    let mut out = String::new();
+
+    for (i, item) in items.iter().enumerate() {
+        match item {
+            ClassItem::Char(ch) => out.push_str(&regex_char(*ch, true)),
+            ClassItem::Property(property) => out.push_str(property),
+            ClassItem::Dash => {
+                let is_range = i > 0
+                    && matches!(items[i - 1], ClassItem::Char(_))
+                    && matches!(items.get(i + 1), Some(ClassItem::Char(_)));
+
+                out.push_str(if is_range { "-" } else { "\\-" });
+            }
+        }
+    }
+
+    out
+}
+
+#[derive(Debug, Clone)]
+enum Lex {
+    Literal(String),
+    Set(String),
+    Range(char, char),
+    Any,
+    Ref(String),
+    Group(Vec<Vec<Lex>>),
+    Repeat(Box<Lex>, String),
+    Not(Box<Lex>),
+}
+
+#[derive(Debug, Clone)]
+struct LexRule {
+    alts: Vec<Vec<Lex>>,
+    fragment: bool,
+    skipped: bool,
+    offset: usize,
+}
+
+struct Parser {
+    toks: Vec<(usize, Tok)>,
+    pos: usize,
+    end: usize,
+    source: Source,
+    lexer: HashMap<String, LexRule>,
+    lexer_order: Vec<String>,
+    regexes: HashMap<String, String>,
+}
+
+impl Parser {
+    fn offset(&self) -> usize {
+        self.toks
+            .get(self.pos)
+            .map_or(self.end, |(offset, _)| *offset)
+    }
+
+    fn peek_at(&self, n: usize) -> Option<&Tok> {
+        self.toks.get(self.pos + n).map(|(_, tok)| tok)
+    }
+
+    fn is(&self, punct: &str) -> bool {
+        matches!(self.peek_at(0), Some(Tok::Punct(p)) if *p == punct)
+    }
+
+    fn is_word(&self, n: usize, word: &str) -> bool {
+        matches!(self.peek_at(n), Some(Tok::Ident(ident)) if ident == word)
+    }
+
+    fn next(&mut self) -> Option<Tok> {
+        let tok = self.peek_at(0).cloned();
+        self.pos += 1;
+        tok
+    }
+
+    fn eat(&mut self, punct: &str) -> bool {
+        if self.is(punct) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        Err(Issue {
+            offset: self.offset(),
+            message: message.into(),
+        })
+    }
+
+    fn expect(&mut self, punct: &str) -> Result<()> {
+        if self.eat(punct) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{punct}`"))
+        }
+    }
+
+    fn ident(&mut self) -> Result<String> {
+        match self.peek_at(0) {
+            Some(Tok::Ident(ident)) => {
+                let ident = ident.clone();
+                self.pos += 1;
+                Ok(ident)
+            }
+            _ => self.error("expected an identifier"),
+        }
+    }
+
+    fn report(&mut self, offset: usize, message: impl Into<String>) {
+        self.source.issues.push(Issue {
+            offset,
+            message: message.into(),
+        });
+    }
+
+    fn skip_past(&mut self, punct: &str) {
+        while self.peek_at(0).is_some() && !self.eat(punct) {
+            self.pos += 1;
+        }
+    }
+
+    fn repeat(&mut self) -> Option<(Repeat, &'static str)> {
+        let repeat = match self.peek_at(0) {
+            Some(Tok::Punct("?")) => (Repeat::Optional, "?"),
+            Some(Tok::Punct("*")) => (Repeat::Star, "*"),
+            Some(Tok::Punct("+")) => (Repeat::Plus, "+"),
+            _ => return None,
+        };
+
+        self.pos += 1;
+        Some(repeat)
+    }
+
+    fn group(alts: Vec<Alt>) -> Sym {
+        if let [Alt { elems, .. }] = &alts[..] {
+            if let [elem] = &elems[..] {
+                return elem.sym.clone();
+            }
+        }
+
+        Sym::Group(alts.into_iter().map(|alt| alt.elems).collect())
+    }
+
+    fn element(&mut self, rule: &str) -> Result<Option<Sym>> {
+        let offset = self.offset();
+
+        if matches!(self.peek_at(0), Some(Tok::Ident(_)))
+            && matches!(self.peek_at(1), Some(Tok::Punct("=" | "+=")))
+        {
+            self.pos += 2;
+        }
+
+        let atom = match self.next() {
+            Some(Tok::Literal(lit)) => Some(Sym::Literal(lit)),
+            Some(Tok::Ident(name)) if name == "EOF" => {
+                self.report(
+                    offset,
+                    "EOF was dropped, the generated parser does not check for the end of input",
+                );
+                None
+            }
+            Some(Tok::Ident(name)) => Some(Sym::NonTerm(name)),
+            Some(Tok::Punct("(")) => {
+                let alts = self.alts(rule)?;
+                self.expect(")")?;
+                Some(Parser::group(alts))
+            }
+            Some(Tok::Punct(".")) => {
+                self.report(offset, "wildcard (.) has no equivalent and was dropped");
+                None
+            }
+            Some(Tok::Punct("~")) => {
+                self.element(rule)?;
+                self.report(offset, "negated set (~) has no equivalent and was dropped");
+                None
+            }
+            Some(Tok::Action(_)) if self.eat("?") => {
+                self.report(offset, "semantic predicate was dropped");
+                return Ok(None);
+            }
+            Some(Tok::Action(_)) => {
+                self.report(offset, "embedded action was dropped");
+                return Ok(None);
+            }
+            _ => {
+                self.pos -= 1;
+                return self.error("expected a rule element");
+            }
+        };
+
+        if let Some(Tok::Set(_)) = self.peek_at(0) {
+            self.pos += 1;
+            self.report(offset, "rule arguments were dropped");
+        }
+
+        if self.is("<") {
+            self.skip_past(">");
+            self.report(offset, "element options were dropped");
+        }
+
+        let mut sym = atom;
+
+        while let Some((repeat, _)) = self.repeat() {
+            if repeat != Repeat::Optional && self.eat("?") {
+                self.report(offset, "non-greedy loop was imported as a greedy one");
+            }
+
+            sym = sym.map(|sym| Sym::Repeat(Box::new(sym), repeat));
+        }
+
+        Ok(sym)
+    }
+
+    fn alt(&mut self, rule: &str) -> Result<Alt> {
+        let offset = self.offset();
+        let mut elems = Vec::new();
+
+        if self.is("<") {
+            self.skip_past(">");
+            self.report(
+                offset,
+                "alternative options such as <assoc=right> were dropped",
+            );
+        }
+
+        while !matches!(
+            self.peek_at(0),
+            None | Some(Tok::Punct(";" | "|" | ")" | "#"))
+        ) {
+            if let Some(sym) = self.element(rule)? {
+                elems.push(Elem {
+                    sym,
+                    name: None,
+                    mutable: false,
+                    selected: false,
+                });
+            }
+        }
+
+        if self.eat("#") {
+            self.ident()?;
+        }
+
+        Ok(Alt {
+            elems,
+            action: Action::Code("()".to_owned()),
+            offset,
+        })
+    }
+
+    fn alts(&mut self, rule: &str) -> Result<Vec<Alt>> {
+        let mut alts = vec![self.alt(rule)?];
+
+        while self.eat("|") {
+            alts.push(self.alt(rule)?);
+        }
+
+        Ok(alts)
+    }
+
+    fn parser_rule(&mut self, name: String, offset: usize) -> Result<()> {
+        while !self.eat(":") {
+            let offset = self.offset();
+
+            match self.next() {
+                Some(Tok::Set(_)) => {
+                    self.report(offset, format!("arguments of {name} were dropped"))
+                }
+                Some(Tok::Ident(word)) if word == "returns" || word == "locals" => {
+                    self.next();
+                    self.report(offset, format!("{word} of {name} were dropped"));
+                }
+                Some(Tok::Ident(word)) if word == "throws" => {
+                    self.ident()?;
+
+                    while self.eat(",") {
+                        self.ident()?;
+                    }
+                }
+                Some(Tok::Ident(word)) if word == "options" => {
+                    self.next();
+                    self.report(offset, format!("options of {name} were ignored"));
+                }
+                Some(Tok::Punct("@")) => {
+                    let action = self.ident()?;
+                    self.next();
+                    self.report(offset, format!("@{action} action of {name} was dropped"));
+                }
+                _ => {
+                    self.pos -= 1;
+                    return self.error("expected `:`");
+                }
+            }
+        }
+
+        let index = self.source.defs.len();
+        let alts = self.alts(&name)?;
+        self.expect(";")?;
+
+        while self.is_word(0, "catch") || self.is_word(0, "finally") {
+            let offset = self.offset();
+            let word = self.ident()?;
+
+            if word == "catch" {
+                self.next();
+            }
+
+            self.next();
+            self.report(offset, format!("{word} handler of {name} was dropped"));
+        }
+
+        self.source.defs.insert(
+            index,
+            Def {
+                name,
+                params: Vec::new(),
+                ty: Some("()".to_owned()),
+                alts,
+                is_pub: false,
+                offset,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn lex_atom(&mut self) -> Result<Option<Lex>> {
+        let offset = self.offset();
+
+        let lex = match self.next() {
+            Some(Tok::Literal(from)) if self.eat("..") => {
+                let to = match self.next() {
+                    Some(Tok::Literal(to)) => to,
+                    _ => return self.error("expected a literal after `..`"),
+                };
+
+                let from: Vec<_> = from.chars().collect();
+                let to: Vec<_> = to.chars().collect();
+
+                match (&from[..], &to[..]) {
+                    ([from], [to]) => Lex::Range(*from, *to),
+                    _ => {
+                        return Err(Issue {
+                            offset,
+                            message: "range bounds must be single characters".to_owned(),
+                        })
+                    }
+                }
+            }
+            Some(Tok::Literal(lit)) => Lex::Literal(lit),
+            Some(Tok::Set(raw)) => Lex::Set(raw),
+            Some(Tok::Punct(".")) => Lex::Any,
+            Some(Tok::Ident(name)) => Lex::Ref(name),
+            Some(Tok::Punct("~")) => match self.lex_atom()? {
+                Some(lex) => Lex::Not(Box::new(lex)),
+                None => return self.error("expected a set after `~`"),
+            },
+            Some(Tok::Punct("(")) => {
+                let mut alts = vec![self.lex_seq()?];
+
+                while self.eat("|") {
+                    alts.push(self.lex_seq()?);
+                }
+
+                self.expect(")")?;
+                Lex::Group(alts)
+            }
+            Some(Tok::Action(_)) => {
+                let what = if self.eat("?") {
+                    "semantic predicate"
+                } else {
+                    "lexer action"
+                };
+                self.report(offset, format!("{what} was dropped"));
+                return Ok(None);
+            }
+            _ => {
+                self.pos -= 1;
+                return self.error("expected a lexer element");
+            }
+        };
+
+        Ok(Some(lex))
+    }
+
+    fn lex_seq(&mut self) -> Result<Vec<Lex>> {
+        let mut seq = Vec::new();
+
+        while !matches!(
+            self.peek_at(0),
+            None | Some(Tok::Punct(";" | "|" | ")" | "->"))
+        ) {
+            let Some(mut lex) = self.lex_atom()? else {
+                continue;
+            };
+
+            while let Some((_, op)) = self.repeat() {
+                let op = if self.eat("?") {
+                    format!("{op}?")
+                } else {
+                    op.to_owned()
+                };
+
+                lex = Lex::Repeat(Box::new(lex), op);
+            }
+
+            seq.push(lex);
+        }
+
+        Ok(seq)
+    }
+
+    fn lexer_rule(&mut self, name: String, fragment: bool, offset: usize) -> Result<()> {
+        self.expect(":")?;
+        let mut alts = Vec::new();
+        let mut skipped = false;
+
+        loop {
+            alts.push(self.lex_seq()?);
+
+            if self.eat("->") {
+                loop {
+                    let offset = self.offset();
+                    let command = self.ident()?;
+
+                    if self.eat("(") {
+                        self.skip_past(")");
+                    }
+
+                    match &command[..] {
+                        "skip" | "channel" => skipped = true,
+                        _ => self.report(offset, format!("lexer command {command} was ignored")),
+                    }
+
+                    if !self.eat(",") {
+                        break;
+                    }
+                }
+            }
+
+            if !self.eat("|") {
+                break;
+            }
+        }
+
+        self.expect(";")?;
+
+        if skipped {
+            self.report(
+                offset,
+                format!(
+                    "skipped lexer rule {name} was dropped, generated parsers only skip whitespace"
+                ),
+            );
+        }
+
+        self.lexer_order.push(name.clone());
+        self.lexer.insert(
+            name,
+            LexRule {
+                alts,
+                fragment,
+                skipped,
+                offset,
+            },
+        );
+
+        Ok(())
+    }
+
+    fn file(&mut self) -> Result<()> {
+        while let Some(tok) = self.peek_at(0).cloned() {
+            let offset = self.offset();
+
+            let word = match tok {
+                Tok::Punct("@") => {
+                    self.pos += 1;
+                    let mut name = self.ident()?;
+
+                    if self.eat("::") {
+                        name = self.ident()?;
+                    }
+
+                    self.next();
+                    self.report(offset, format!("@{name} action was dropped"));
+                    continue;
+                }
+                Tok::Ident(word) => word,
+                _ => return self.error("expected a rule"),
+            };
+
+            let next_is_ident = matches!(self.peek_at(1), Some(Tok::Ident(_)));
+            let next_is_action = matches!(self.peek_at(1), Some(Tok::Action(_)));
+
+            match &word[..] {
+                "grammar" if next_is_ident => self.skip_past(";"),
+                "parser" | "lexer" if self.is_word(1, "grammar") => self.skip_past(";"),
+                "import" if next_is_ident => {
+                    self.skip_past(";");
+                    self.report(offset, "grammar imports are not supported");
+                }
+                "mode" if next_is_ident => {
+                    self.skip_past(";");
+                    self.report(
+                        offset,
+                        "lexer modes are not supported, the following rules were imported into the default mode",
+                    );
+                }
+                "options" | "tokens" | "channels" if next_is_action => {
+                    self.pos += 2;
+                    self.report(offset, format!("{word} section was ignored"));
+                }
+                "fragment" if next_is_ident => {
+                    self.pos += 1;
+                    let name = self.ident()?;
+                    self.lexer_rule(name, true, offset)?;
+                }
+                _ => {
+                    self.pos += 1;
+
+                    if word.starts_with(|ch: char| ch.is_ascii_uppercase()) {
+                        self.lexer_rule(word, false, offset)?;
+                    } else {
+                        self.parser_rule(word, offset)?;
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn rule_regex(&mut self, name: &str, stack: &mut Vec<String>) -> String {
+        if let Some(re) = self.regexes.get(name) {
+            return re.clone();
+        }
+
+        let rule = self.lexer[name].clone();
+
+        if stack.iter().any(|other| other == name) {
+            self.report(
+                rule.offset,
+                format!("recursive lexer rule {name} cannot be converted to a regex"),
+            );
+            return String::new();
+        }
+
+        stack.push(name.to_owned());
+        let re = self.alts_regex(&rule.alts, rule.offset, stack);
+        stack.pop();
+
+        self.regexes.insert(name.to_owned(), re.clone());
+        re
+    }
+
+    fn alts_regex(&mut self, alts: &[Vec<Lex>], offset: usize, stack: &mut Vec<String>) -> String {
+        let alts: Vec<String> = alts
+            .iter()
+            .map(|seq| {
+                seq.iter()
+                    .map(|lex| self.regex(lex, offset, stack))
+                    .collect()
+            })
+            .collect();
+
+        match &alts[..] {
+            [one] => one.clone(),
+            alts => format!("(?:{})", alts.join("|")),
+        }
+    }
+
+    fn negated(lex: &Lex) -> Option<String> {
+        match lex {
+            Lex::Literal(lit) if lit.chars().count() == 1 => {
+                lit.chars().next().map(|ch| regex_char(ch, true))
+            }
+            Lex::Set(raw) => Some(class(raw)),
+            Lex::Range(from, to) => Some(format!(
+                "{}-{}",
+                regex_char(*from, true),
+                regex_char(*to, true)
+            )),
+            Lex::Group(alts) => alts
+                .iter()
+                .map(|seq| match &seq[..] {
+                    [lex] => Parser::negated(lex),
+                    _ => None,
+                })
+                .collect(),
+            _ => None,
+        }
+    }
+
+    fn regex(&mut self, lex: &Lex, offset: usize, stack: &mut Vec<String>) -> String {
+        match lex {
+            Lex::Literal(lit) => lit.chars().map(|ch| regex_char(ch, false)).collect(),
+            Lex::Set(raw) => format!("[{}]", class(raw)),
+            Lex::Range(from, to) => {
+                format!("[{}-{}]", regex_char(*from, true), regex_char(*to, true))
+            }
+            Lex::Any => "(?s:.)".to_owned(),
+            Lex::Ref(name) if self.lexer.contains_key(name) => {
+                format!("(?:{})", self.rule_regex(name, stack))
+            }
+            Lex::Ref(name) => {
+                self.report(offset, format!("undefined lexer rule {name}"));
+                String::new()
+            }
+            Lex::Group(alts) => self.alts_regex(alts, offset, stack),
+            Lex::Repeat(inner, op) => {
+                let re = self.regex(inner, offset, stack);
+                let atomic = match &**inner {
+                    Lex::Literal(lit) => lit.chars().count() == 1,
+                    Lex::Group(alts) => alts.len() > 1,
+                    Lex::Repeat(_, _) => false,
+                    _ => true,
+                };
+
+                if atomic {
+                    format!("{re}{op}")
+                } else {
+                    format!("(?:{re}){op}")
+                }
+            }
+            Lex::Not(inner) => match Parser::negated(inner) {
+                Some(class) => format!("[^{class}]"),
+                None => {
+                    self.report(offset, "only sets and single characters can be negated");
+                    String::new()
+                }
+            },
+        }
+    }
+
+    fn token(&mut self, name: &str, offset: usize) -> Sym {
+        let Some(rule) = self.lexer.get(name).cloned() else {
+            self.report(offset, format!("token {name} has no lexer rule"));
+            return Sym::Literal(name.to_owned());
+        };
+
+        if rule.fragment {
+            self.report(
+                offset,
+                format!("fragment {name} cannot be used in a parser rule"),
+            );
+        }
+
+        if let [seq] = &rule.alts[..] {
+            if let [Lex::Literal(lit)] = &seq[..] {
+                return Sym::Literal(lit.clone());
+            }
+        }
+
+        match self.rule_regex(name, &mut Vec::new()) {
+            re if re.is_empty() => Sym::Literal(name.to_owned()),
+            re => Sym::Token(name.to_owned(), re),
+        }
+    }
+
+    fn resolve(&mut self, sym: &mut Sym, offset: usize) {
+        match sym {
+            Sym::NonTerm(name) if name.starts_with(|ch: char| ch.is_ascii_uppercase()) => {
+                *sym = self.token(&name.clone(), offset);
+            }
+            Sym::Repeat(inner, _) => self.resolve(inner, offset),
+            Sym::Group(alts) => {
+                for elem in alts.iter_mut().flatten() {
+                    self.resolve(&mut elem.sym, offset);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // ANTLR lexes the longest match while generated parsers try tokens in
+    // order, so a regex that only ever loses to a later one has to move behind
+    // it. When both win somewhere no order works and it is reported.
+    fn longest_first(&mut self, tokens: Vec<(Sym, usize)>) -> Vec<Sym> {
+        let regexes: Vec<_> = tokens
+            .iter()
+            .map(|(sym, _)| match sym {
+                Sym::Token(name, re) => Some((name.clone(), re.clone())),
+                _ => None,
+            })
+            .collect();
+        let matchers: Vec<_> = regexes
+            .iter()
+            .map(|regex| Regex::new(&format!("\\A(?:{})", regex.as_ref()?.1)).ok())
+            .collect();
+        let samples: Vec<Vec<String>> = regexes
+            .iter()
+            .map(|regex| {
+                let Some(hir) = regex
+                    .as_ref()
+                    .and_then(|(_, re)| regex_syntax::parse(re).ok())
+                else {
+                    return Vec::new();
+                };
+
+                (0..LONGEST_MATCH_SAMPLES)
+                    .map(|seed| {
+                        let mut sample = String::new();
+                        generate(&hir, &mut Rng::new(seed), &mut sample);
+                        sample
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let len = |i: usize, text: &str| Some(matchers[i].as_ref()?.find(text)?.end());
+        // Whether each of two tokens wins on some sample, ties going to the
+        // earlier one like in ANTLR.
+        let wins =
+            |later: usize, earlier: usize| {
+                samples[later].iter().chain(&samples[earlier]).fold(
+                    (false, false),
+                    |(l, e), sample| match (len(later, sample), len(earlier, sample)) {
+                        (Some(a), Some(b)) => (l || a > b, e || b >= a),
+                        _ => (l, e),
+                    },
+                )
+            };
+
+        let mut order: Vec<usize> = Vec::new();
+
+        for later in 0..tokens.len() {
+            let mut at = order.len();
+
+            for (pos, &earlier) in order.iter().enumerate().rev() {
+                let (later_wins, earlier_wins) = wins(later, earlier);
+
+                let (true, Some((name, _)), Some((other, _))) =
+                    (later_wins, &regexes[later], &regexes[earlier])
+                else {
+                    continue;
+                };
+
+                let message = if earlier_wins {
+                    format!("{name} and {other} overlap in a way no token order resolves")
+                } else {
+                    at = pos;
+                    format!("{name} was moved before {other} since ANTLR prefers the longest match")
+                };
+
+                self.report(tokens[later].1, message);
+            }
+
+            order.insert(at, later);
+        }
+
+        let mut tokens: Vec<_> = tokens.into_iter().map(|(sym, _)| Some(sym)).collect();
+        order.into_iter().filter_map(|i| tokens[i].take()).collect()
+    }
+
+    fn finish(mut self) -> Source {
+        let mut defs = std::mem::take(&mut self.source.defs);
+
+        for def in defs.iter_mut() {
+            for alt in def.alts.iter_mut() {
+                for elem in alt.elems.iter_mut() {
+                    self.resolve(&mut elem.sym, alt.offset);
+                }
+            }
+        }
+
+        let mut tokens = Vec::new();
+
+        for name in self.lexer_order.clone() {
+            let rule = &self.lexer[&name];
+
+            if !rule.fragment && !rule.skipped {
+                let offset = rule.offset;
+                tokens.push((self.token(&name, offset), offset));
+            }
+        }
+
+        self.source.tokens = self.longest_first(tokens);
+
+        let referenced: HashSet<String> = defs
+            .iter()
+            .flat_map(|def| {
+                def.alts
+                    .iter()
+                    .flat_map(|alt| alt.elems.iter())
+                    .flat_map(|elem| nonterms(&elem.sym))
+                    .filter(move |name| *name != def.name)
+            })
+            .collect();
+
+        for def in defs.iter_mut() {
+            def.is_pub = !referenced.contains(&def.name);
+        }
+
+        if let (false, Some(first)) = (defs.iter().any(|def| def.is_pub), defs.first_mut()) {
+            first.is_pub = true;
+        }
+
+        self.source.defs = defs;
+        self.source
+    }
+}
+
+fn nonterms(sym: &Sym) -> Vec<String> {
+    match sym {
+        Sym::NonTerm(name) => vec![name.clone()],
+        Sym::Repeat(inner, _) => nonterms(inner),
+        Sym::Group(alts) => alts
+            .iter()
+            .flatten()
+            .flat_map(|elem| nonterms(&elem.sym))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+pub fn import(input: &str) -> std::result::Result<Import, Issue> {
+    let toks = tokenize(input)?;
+
+    let mut parser = Parser {
+        toks,
+        pos: 0,
+        end: input.len(),
+        source: Source::default(),
+        lexer: HashMap::new(),
+        lexer_order: Vec::new(),
+        regexes: HashMap::new(),
+    };
+
+    parser.file()?;
+    Ok(lower(&parser.finish()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        import::Import,
+        lexer::{Term, TokDesc},
+        notation::format::format,
+        parse_language,
+    };
+
+    const STATEMENTS: &str = r#"grammar T;
+
+stmt : 'let' ID ('=' expr)? ';' | expr ';' ;
+expr : atom (('+' | '-') atom)* ;
+atom : INT+ | '(' expr ')' ;
+
+ID : LETTER (LETTER | DIGIT)* ;
+INT : DIGIT+ ;
+fragment LETTER : [a-zA-Z_] ;
+fragment DIGIT : [0-9] ;
+WS : [ \t]+ -> skip ;
+"#;
+
+    fn arms(imported: &Import) -> Vec<String> {
+        imported
+            .language
+            .grammar
+            .nonterms
+            .iter()
+            .flat_map(|def| {
+                (0..def.rules.len()).map(move |r| match def.is_pub {
+                    true => format!("pub {}", def.label(r)),
+                    false => def.label(r),
+                })
+            })
+            .collect()
+    }
+
+    fn messages(imported: &Import) -> Vec<&str> {
+        imported
+            .issues
+            .iter()
+            .map(|issue| &issue.message[..])
+            .collect()
+    }
+
+    fn order(grammar: &str) -> Vec<String> {
+        let imported = super::import(grammar).unwrap();
+
+        imported
+            .language
+            .lexer
+            .mapping
+            .into_iter()
+            .map(|(Term(name), _, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn longer_tokens_move_first() {
+        let tokens =
+            order("grammar G;\ns : INT | FLOAT ;\nINT : [0-9]+ ;\nFLOAT : [0-9]+ '.' [0-9]* ;\n");
+        assert_eq!(tokens, ["FLOAT", "INT"]);
+    }
+
+    #[test]
+    fn ties_keep_declaration_order() {
+        let grammar = "grammar G;\ns : A | B ;\nA : 'a'+ 'b'? ;\nB : 'a'* 'b'+ ;\n";
+        let imported = super::import(grammar).unwrap();
+
+        assert_eq!(order(grammar), ["A", "B"]);
+        assert!(imported
+            .issues
+            .iter()
+            .any(|issue| issue.message.contains("no token order")));
+    }
+
+    #[test]
+    fn round_trips_rules_and_tokens() {
+        let imported = super::import(
+            r#"grammar Small;
+
+list : '[' item (',' item)* ']' EOF ;
+item : ID | INT | list ;
+
+ID : [a-z]+ ;
+INT : DIGIT+ ;
+fragment DIGIT : [0-9] ;
+WS : [ \t\r\n]+ -> skip ;
+"#,
+        )
+        .unwrap();
+        let language = parse_language(&format(&imported.language, "")).unwrap();
+
+        let arms: Vec<_> = language
+            .grammar
+            .written()
+            .into_iter()
+            .flat_map(|def| (0..def.rules.len()).map(|r| def.label(r)))
+            .collect();
+        let tokens: Vec<_> = language
+            .lexer
+            .mapping
+            .iter()
+            .map(|(term, desc, _)| match desc {
+                TokDesc::Token(lit) => format!("{} token {lit}", term.0),
+                TokDesc::Regex(re) => format!("{} regex {re}", term.0),
+            })
+            .collect();
+
+        assert_eq!(
+            arms,
+            [
+                r#"list 1: "[" item ("," item)* "]""#,
+                r#"item 1: "ID""#,
+                r#"item 2: "INT""#,
+                "item 3: list",
+            ]
+        );
+        assert_eq!(
+            tokens,
+            [
+                "[ token [",
+                ", token ,",
+                "] token ]",
+                r"ID regex \A[a-z]+",
+                r"INT regex \A(?:[0-9])+",
+            ]
+        );
+        assert_eq!(
+            messages(&imported),
+            [
+                "EOF was dropped, the generated parser does not check for the end of input",
+                "skipped lexer rule WS was dropped, generated parsers only skip whitespace",
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_unsupported_constructs() {
+        let imported = super::import(
+            r#"grammar Warn;
+
+options { language = Java; }
+
+@header { package x; }
+
+s : {isOk()}? A {act();} | . | ~A ;
+
+A : 'a' -> channel(HIDDEN) ;
+
+mode OTHER;
+B : 'b' ;
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            messages(&imported),
+            [
+                "options section was ignored",
+                "@header action was dropped",
+                "semantic predicate was dropped",
+                "embedded action was dropped",
+                "wildcard (.) has no equivalent and was dropped",
+                "negated set (~) has no equivalent and was dropped",
+                "skipped lexer rule A was dropped, generated parsers only skip whitespace",
+                "lexer modes are not supported, the following rules were imported into the default mode",
+            ]
+        );
+        assert!(parse_language(&format(&imported.language, "")).is_ok());
+    }
+
+    #[test]
+    fn translates_alternatives_and_repetitions() {
+        let imported = super::import(STATEMENTS).unwrap();
+
+        assert_eq!(
+            arms(&imported),
+            [
+                r#"pub stmt 1: "let" "ID" ("=" expr)? ";""#,
+                r#"pub stmt 2: expr ";""#,
+                r#"expr 1: atom (("+" | "-") atom)*"#,
+                r#"atom 1: "INT"+"#,
+                r#"atom 2: "(" expr ")""#,
+            ]
+        );
+        assert!(parse_language(&format(&imported.language, "")).is_ok());
+    }
+
+    #[test]
+    fn inlines_fragments() {
+        let imported = super::import(STATEMENTS).unwrap();
+        let regexes: Vec<_> = imported
+            .language
+            .lexer
+            .mapping
+            .iter()
+            .filter_map(|(term, desc, _)| match desc {
+                TokDesc::Regex(re) => Some((&term.0[..], &re[..])),
+                TokDesc::Token(_) => None,
+            })
+            .collect();
+
+        assert_eq!(
+            regexes,
+            [
+                ("ID", r"\A(?:[a-zA-Z_])(?:(?:[a-zA-Z_])|(?:[0-9]))*"),
+                ("INT", r"\A(?:[0-9])+"),
+            ]
+        );
+    }
+
+    #[test]
+    fn drops_skipped_tokens() {
+        let imported = super::import(STATEMENTS).unwrap();
+
+        assert!(order(STATEMENTS).iter().all(|name| name != "WS"));
+        assert_eq!(
+            messages(&imported),
+            ["skipped lexer rule WS was dropped, generated parsers only skip whitespace"]
+        );
+    }
+}
@@ -0,0 +1,703 @@
+use super::{lower, Action, Alt, Def, Elem, Import, Issue, Repeat, Source, Sym};
+
+struct Parser<'s> {
+    input: &'s str,
+    pos: usize,
+    source: Source,
+}
+
+type Result<T> = std::result::Result<T, Issue>;
+
+impl<'s> Parser<'s> {
+    fn rest(&self) -> &'s str {
+        &self.input[self.pos..]
+    }
+
+    fn error<T>(&self, message: impl Into<String>) -> Result<T> {
+        Err(Issue {
+            offset: self.pos,
+            message: message.into(),
+        })
+    }
+
+    fn report(&mut self, offset: usize, message: impl Into<String>) {
+        self.source.issues.push(Issue {
+            offset,
+            message: message.into(),
+        });
+    }
+
+    fn skip(&mut self) {
+        loop {
+            let rest = self.rest();
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self, text: &str) -> bool {
+        self.skip();
+        self.rest().starts_with(text)
+    }
+
+    fn eat(&mut self, text: &str) -> bool {
+        if self.peek(text) {
+            self.pos += text.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, text: &str) -> Result<()> {
+        if self.eat(text) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{text}`"))
+        }
+    }
+
+    fn peek_ident(&mut self) -> Option<&'s str> {
+        self.skip();
+        let rest = self.rest();
+        let len = rest
+            .find(|ch: char| ch != '_' && !ch.is_alphanumeric())
+            .unwrap_or(rest.len());
+
+        match rest.chars().next() {
+            Some(ch) if ch == '_' || ch.is_alphabetic() => Some(&rest[..len]),
+            _ => None,
+        }
+    }
+
+    fn ident(&mut self) -> Result<&'s str> {
+        match self.peek_ident() {
+            Some(ident) => {
+                self.pos += ident.len();
+                Ok(ident)
+            }
+            None => self.error("expected an identifier"),
+        }
+    }
+
+    fn eat_word(&mut self, word: &str) -> bool {
+        if self.peek_ident() == Some(word) {
+            self.pos += word.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn string(&mut self) -> Result<String> {
+        let start = self.pos;
+        self.pos += 1;
+        let mut out = String::new();
+        let mut chars = self.rest().char_indices();
+
+        while let Some((i, ch)) = chars.next() {
+            match ch {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(out);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => out.push('\n'),
+                    Some((_, 't')) => out.push('\t'),
+                    Some((_, 'r')) => out.push('\r'),
+                    Some((_, '0')) => out.push('\0'),
+                    Some((_, ch)) => out.push(ch),
+                    None => break,
+                },
+                ch => out.push(ch),
+            }
+        }
+
+        self.pos = start;
+        self.error("unterminated string")
+    }
+
+    fn raw_string(&mut self) -> Result<String> {
+        self.pos += 1;
+        let hashes = self.rest().len() - self.rest().trim_start_matches('#').len();
+        self.pos += hashes;
+        self.expect("\"")?;
+
+        let close = format!("\"{}", "#".repeat(hashes));
+
+        match self.rest().find(&close) {
+            Some(end) => {
+                let text = self.rest()[..end].to_owned();
+                self.pos += end + close.len();
+                Ok(text)
+            }
+            None => self.error("unterminated raw string"),
+        }
+    }
+
+    fn code(&mut self, stop: impl Fn(&str) -> bool) -> Result<String> {
+        self.skip();
+        let start = self.pos;
+        let mut depth = 0usize;
+
+        while let Some(ch) = self.rest().chars().next() {
+            let rest = self.rest();
+
+            if depth == 0 && stop(rest) {
+                break;
+            }
+
+            match ch {
+                '(' | '[' | '{' => depth += 1,
+                ')' | ']' | '}' if depth == 0 => break,
+                ')' | ']' | '}' => depth -= 1,
+                '"' => {
+                    self.string()?;
+                    continue;
+                }
+                'r' if rest[1..].starts_with('"') || rest[1..].starts_with("#\"") => {
+                    self.raw_string()?;
+                    continue;
+                }
+                '\'' => {
+                    let mut chars = rest[1..].chars();
+                    let len = match (chars.next(), chars.next()) {
+                        (Some('\\'), _) => rest[2..].find('\'').map(|end| end + 3),
+                        (Some(ch), Some('\'')) => Some(ch.len_utf8() + 2),
+                        _ => None,
+                    };
+
+                    if let Some(len) = len {
+                        self.pos += len;
+                        continue;
+                    }
+                }
+                '/' if rest.starts_with("//") => {
+                    self.pos += rest.find('\n').unwrap_or(rest.len());
+                    continue;
+                }
+                _ => {}
+            }
+
+            self.pos += ch.len_utf8();
+        }
+
+        Ok(self.input[start..self.pos].trim().to_owned())
+    }
+
+    fn action(&mut self, stop: impl Fn(&str) -> bool) -> Result<String> {
+        self.skip();
+        let line_start = self.input[..self.pos].rfind('\n').map_or(0, |i| i + 1);
+        let line = &self.input[line_start..self.pos];
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let code = format!("{indent}{}", self.code(stop)?);
+
+        let dedent = code
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| line.len() - line.trim_start().len())
+            .min()
+            .unwrap_or(0);
+
+        let lines: Vec<_> = code
+            .lines()
+            .map(|line| line.get(dedent..).unwrap_or_default().trim_end())
+            .collect();
+
+        Ok(lines.join("\n"))
+    }
+
+    fn block(&mut self) -> Result<()> {
+        self.expect("{")?;
+        self.code(|_| false)?;
+        self.expect("}")
+    }
+
+    fn ty(&mut self) -> Result<String> {
+        let mut angle = 0usize;
+
+        let text = self.code(|rest| {
+            rest.starts_with('=') && !rest.starts_with("=>") && !rest.starts_with("==")
+        })?;
+
+        for (i, ch) in text.char_indices() {
+            match ch {
+                '<' => angle += 1,
+                '>' if !text[..i].ends_with('-') => angle = angle.saturating_sub(1),
+                _ => {}
+            }
+        }
+
+        if angle != 0 {
+            return self.error("unbalanced `<` in type");
+        }
+
+        Ok(text)
+    }
+
+    fn primary(&mut self) -> Result<Option<Sym>> {
+        self.skip();
+        let rest = self.rest();
+
+        if rest.starts_with('"') {
+            return Ok(Some(Sym::Literal(self.string()?)));
+        }
+
+        if rest.starts_with("r\"") || rest.starts_with("r#") {
+            return Ok(Some(Sym::Regex(self.raw_string()?)));
+        }
+
+        if self.eat("@L") || self.eat("@R") {
+            return Ok(Some(Sym::Location));
+        }
+
+        if self.eat("!") {
+            return Ok(Some(Sym::Error));
+        }
+
+        if self.eat("(") {
+            let elems = self.elems(&[")"])?;
+            self.expect(")")?;
+            return Ok(Some(Sym::Group(vec![elems])));
+        }
+
+        let Some(name) = self.peek_ident() else {
+            return Ok(None);
+        };
+
+        if name == "if" {
+            return Ok(None);
+        }
+
+        self.pos += name.len();
+
+        if self.rest().starts_with('<') {
+            self.pos += 1;
+            let mut args = Vec::new();
+
+            while !self.eat(">") {
+                match self.symbol()? {
+                    Some(sym) => args.push(sym),
+                    None => return self.error("expected a macro argument"),
+                }
+
+                if !self.eat(",") && !self.peek(">") {
+                    return self.error("expected `,` or `>`");
+                }
+            }
+
+            return Ok(Some(Sym::Macro(name.to_owned(), args)));
+        }
+
+        Ok(Some(Sym::NonTerm(name.to_owned())))
+    }
+
+    fn symbol(&mut self) -> Result<Option<Sym>> {
+        let Some(mut sym) = self.primary()? else {
+            return Ok(None);
+        };
+
+        loop {
+            let repeat = match self.rest().chars().next() {
+                Some('?') => Repeat::Optional,
+                Some('*') => Repeat::Star,
+                Some('+') => Repeat::Plus,
+                _ => break,
+            };
+
+            self.pos += 1;
+            sym = Sym::Repeat(Box::new(sym), repeat);
+        }
+
+        Ok(Some(sym))
+    }
+
+    fn elem(&mut self) -> Result<Option<Elem>> {
+        if !self.peek("<") {
+            return Ok(self.symbol()?.map(|sym| Elem {
+                sym,
+                name: None,
+                mutable: false,
+                selected: false,
+            }));
+        }
+
+        self.pos += 1;
+        let save = self.pos;
+        let mutable = self.eat_word("mut");
+
+        let name = match self.peek_ident() {
+            Some(ident)
+                if self.rest()[ident.len()..].starts_with(':')
+                    && !self.rest()[ident.len()..].starts_with("::") =>
+            {
+                self.pos += ident.len() + 1;
+                Some(ident.to_owned())
+            }
+            _ => {
+                self.pos = save;
+                None
+            }
+        };
+
+        let elems = self.elems(&[">"])?;
+        self.expect(">")?;
+
+        let sym = match <[Elem; 1]>::try_from(elems) {
+            Ok([elem]) if !elem.selected => elem.sym,
+            Ok([elem]) => Sym::Group(vec![vec![elem]]),
+            Err(elems) => Sym::Group(vec![elems]),
+        };
+
+        Ok(Some(Elem {
+            sym,
+            mutable: mutable && name.is_some(),
+            name,
+            selected: true,
+        }))
+    }
+
+    fn elems(&mut self, end: &[&str]) -> Result<Vec<Elem>> {
+        let mut elems = Vec::new();
+
+        loop {
+            self.skip();
+
+            if end.iter().any(|end| self.rest().starts_with(end)) || self.rest().starts_with("=>") {
+                return Ok(elems);
+            }
+
+            match self.elem()? {
+                Some(elem) => elems.push(elem),
+                None => return Ok(elems),
+            }
+        }
+    }
+
+    fn alt(&mut self) -> Result<Alt> {
+        while self.peek("#[") {
+            let offset = self.pos;
+            self.pos += 1;
+            let attr = self.code(|rest| rest.starts_with(']'))?;
+            self.report(offset, format!("attribute #{attr}] was dropped"));
+        }
+
+        self.skip();
+        let offset = self.pos;
+        let elems = self.elems(&[",", "}", ";"])?;
+
+        if self.eat_word("if") {
+            let offset = self.pos;
+            let cond = self.code(|rest| rest.starts_with("=>"))?;
+            self.report(offset, format!("macro condition `if {cond}` was ignored"));
+        }
+
+        let stop = |rest: &str| rest.starts_with(',') || rest.starts_with(';');
+
+        let action = if self.eat("=>@L") || self.eat("=>@R") {
+            Action::Location
+        } else if self.eat("=>?") {
+            Action::Fallible(self.action(stop)?)
+        } else if self.eat("=>") {
+            Action::Code(self.action(stop)?)
+        } else {
+            Action::Value
+        };
+
+        Ok(Alt {
+            elems,
+            action,
+            offset,
+        })
+    }
+
+    fn def(&mut self, is_pub: bool) -> Result<Def> {
+        self.skip();
+        let offset = self.pos;
+        let name = self.ident()?.to_owned();
+        let mut params = Vec::new();
+
+        if self.rest().starts_with('<') {
+            self.pos += 1;
+
+            while !self.eat(">") {
+                params.push(self.ident()?.to_owned());
+
+                if !self.eat(",") && !self.peek(">") {
+                    return self.error("expected `,` or `>`");
+                }
+            }
+        }
+
+        let ty = if self.eat(":") {
+            Some(self.ty()?)
+        } else {
+            None
+        };
+        self.expect("=")?;
+
+        let mut alts = Vec::new();
+
+        if self.eat("{") {
+            while !self.eat("}") {
+                alts.push(self.alt()?);
+
+                if !self.eat(",") && !self.peek("}") {
+                    return self.error("expected `,` or `}`");
+                }
+            }
+
+            self.eat(";");
+        } else {
+            alts.push(self.alt()?);
+            self.expect(";")?;
+        }
+
+        Ok(Def {
+            name,
+            params,
+            ty,
+            alts,
+            is_pub,
+            offset,
+        })
+    }
+
+    fn file(&mut self) -> Result<()> {
+        loop {
+            self.skip();
+            let offset = self.pos;
+
+            if self.rest().is_empty() {
+                return Ok(());
+            }
+
+            if self.eat("#[") {
+                let attr = self.code(|rest| rest.starts_with(']'))?;
+                self.expect("]")?;
+
+                if attr != "inline" {
+                    self.report(offset, format!("attribute #[{attr}] was dropped"));
+                }
+
+                continue;
+            }
+
+            match self.peek_ident() {
+                Some("use") => {
+                    let text = self.code(|rest| rest.starts_with(';'))?;
+                    self.expect(";")?;
+                    self.source.preamble.push(format!("{text};"));
+                }
+                Some("grammar") => {
+                    let text = self.code(|rest| rest.starts_with(';'))?;
+                    self.expect(";")?;
+
+                    if text != "grammar" {
+                        self.report(
+                            offset,
+                            "grammar parameters and type parameters are not supported",
+                        );
+                    }
+                }
+                Some("extern") => {
+                    self.pos += "extern".len();
+                    self.block()?;
+                    self.report(
+                        offset,
+                        "extern token declarations are not supported, terminals were imported as literal tokens",
+                    );
+                }
+                Some("match") => {
+                    self.pos += "match".len();
+                    self.block()?;
+
+                    if self.eat_word("else") {
+                        self.block()?;
+                    }
+
+                    self.report(
+                        offset,
+                        "match blocks are not supported, tokens are tried in declaration order",
+                    );
+                }
+                Some("pub") => {
+                    self.pos += "pub".len();
+                    let def = self.def(true)?;
+                    self.source.defs.push(def);
+                }
+                Some(_) => {
+                    let def = self.def(false)?;
+                    self.source.defs.push(def);
+                }
+                None => return self.error("expected a nonterminal definition"),
+            }
+        }
+    }
+}
+
+pub fn import(input: &str) -> std::result::Result<Import, Issue> {
+    let mut parser = Parser {
+        input,
+        pos: 0,
+        source: Source::default(),
+    };
+
+    parser.file()?;
+
+    for def in parser
+        .source
+        .defs
+        .iter()
+        .filter(|def| def.is_pub && !def.params.is_empty())
+    {
+        parser.source.issues.push(Issue {
+            offset: def.offset,
+            message: format!("macro {} cannot be public", def.name),
+        });
+    }
+
+    Ok(lower(&parser.source))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{import::Import, lexer::TokDesc, notation::format::format, parse_language};
+
+    fn import(grammar: &str) -> Import {
+        super::import(grammar).unwrap()
+    }
+
+    fn messages(imported: &Import) -> Vec<&str> {
+        imported
+            .issues
+            .iter()
+            .map(|issue| &issue.message[..])
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_rules_and_tokens() {
+        let imported = import(
+            r#"use std::str::FromStr;
+
+grammar;
+
+pub Expr: i32 = {
+    <l:Expr> "+" <r:Term> => l + r,
+    Term,
+};
+
+Term: i32 = {
+    <n:Num> => n,
+    "(" <Expr> ")",
+};
+
+Num: i32 = r"[0-9]+" => i32::from_str(<>).unwrap();
+
+Comma<T>: Vec<T> = {
+    <mut v:(<T> ",")*> <e:T?> => match e {
+        None => v,
+        Some(e) => { v.push(e); v }
+    }
+};
+
+pub List = "[" <Comma<Num>> "]";
+"#,
+        );
+        assert!(imported.issues.is_empty());
+
+        let language = parse_language(&format(&imported.language, "")).unwrap();
+        let defs: Vec<_> = language
+            .grammar
+            .written()
+            .into_iter()
+            .map(|def| {
+                format!(
+                    "{} {} {}",
+                    def.is_pub,
+                    def.name.0,
+                    def.ret_ty.replace(' ', "")
+                )
+            })
+            .collect();
+        let tokens: Vec<_> = language
+            .lexer
+            .mapping
+            .iter()
+            .map(|(term, desc, _)| match desc {
+                TokDesc::Token(lit) => format!("{} token {lit}", term.0),
+                TokDesc::Regex(re) => format!("{} regex {re}", term.0),
+            })
+            .collect();
+
+        assert_eq!(language.preamble.trim(), "use std::str::FromStr;");
+        assert_eq!(
+            defs,
+            [
+                "true Expr i32",
+                "false Term i32",
+                "false Num i32",
+                "true List Vec<i32>",
+                "false Comma_Num Vec<i32>",
+            ]
+        );
+        assert_eq!(language.grammar.macros[0].params, ["T"]);
+        assert_eq!(
+            tokens,
+            [
+                "+ token +",
+                "( token (",
+                ") token )",
+                ", token ,",
+                "[ token [",
+                "] token ]",
+                r"[0-9]+ regex \A[0-9]+",
+            ]
+        );
+        assert_eq!(
+            language.grammar.nonterms[0].rules[0].arm(),
+            r#"Expr "+" Term"#
+        );
+    }
+
+    #[test]
+    fn warns_about_unsupported_constructs() {
+        let imported = import(
+            r#"grammar;
+
+#[inline]
+pub Start: usize = {
+    <l:@L> "a" <r:@R> => r - l,
+    "b" =>? Ok(1),
+    ! => 0,
+    "c" =>@L,
+};
+"#,
+        );
+
+        assert_eq!(
+            messages(&imported),
+            [
+                "@L and @R have no equivalent, using 0",
+                "fallible action (=>?) was converted to .unwrap()",
+                "error recovery (!) has no equivalent and was dropped",
+                "location action (=>@L, =>@R) has no equivalent, using 0",
+            ]
+        );
+        assert!(parse_language(&format(&imported.language, "")).is_ok());
+    }
+
+    #[test]
+    fn refuses_public_macros() {
+        let imported = import("grammar;\n\npub Many<T>: Vec<T> = T* => <>;\n");
+
+        assert_eq!(messages(&imported), ["macro Many cannot be public"]);
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::{
+    ast::{Grammar, Item, Language, MacroDef, Node, NonTerm, NonTermDef, Repeat, Rule, Span},
+    lexer::{Term, TokDesc, Tokens},
+};
+
+pub mod antlr;
+pub mod lalrpop;
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub offset: usize,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Import {
+    pub language: Language,
+    pub issues: Vec<Issue>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Sym {
+    Literal(String),
+    Regex(String),
+    Token(String, String),
+    NonTerm(String),
+    Macro(String, Vec<Sym>),
+    Group(Vec<Vec<Elem>>),
+    Repeat(Box<Sym>, Repeat),
+    Location,
+    Error,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Elem {
+    pub sym: Sym,
+    pub name: Option<String>,
+    pub mutable: bool,
+    pub selected: bool,
+}
+
+#[derive(Debug, Clone)]
+pub enum Action {
+    Value,
+    Code(String),
+    Fallible(String),
+    Location,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alt {
+    pub elems: Vec<Elem>,
+    pub action: Action,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Def {
+    pub name: String,
+    pub params: Vec<String>,
+    pub ty: Option<String>,
+    pub alts: Vec<Alt>,
+    pub is_pub: bool,
+    pub offset: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Source {
+    pub preamble: Vec<String>,
+    pub tokens: Vec<Sym>,
+    pub defs: Vec<Def>,
+    pub issues: Vec<Issue>,
+}
+
+const STR: &str = "&'input str";
+
+fn replace_word(text: &str, word: &str, with: &str) -> String {
+    let mut out = String::new();
+    let mut rest = text;
+
+    while let Some(i) = rest.find(word) {
+        let before = rest[..i].chars().next_back();
+        let after = rest[i + word.len()..].chars().next();
+        let is_ident = |ch: Option<char>| ch.is_some_and(|ch| ch == '_' || ch.is_alphanumeric());
+
+        out.push_str(&rest[..i]);
+        out.push_str(if is_ident(before) || is_ident(after) {
+            word
+        } else {
+            with
+        });
+        rest = &rest[i + word.len()..];
+    }
+
+    out.push_str(rest);
+    out
+}
+
+const KEYWORDS: &[&str] = &["preamble", "tokens", "rules", "pub", "token", "regex"];
+
+fn unreserved(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_owned()
+    }
+}
+
+struct Lowering<'s> {
+    source: &'s Source,
+    defs: HashMap<&'s str, &'s Def>,
+    tokens: Vec<(Term, TokDesc)>,
+    types: HashMap<String, Option<String>>,
+    inferring: HashSet<String>,
+    params: Vec<String>,
+    issues: Vec<Issue>,
+}
+
+impl<'s> Lowering<'s> {
+    fn new(source: &'s Source) -> Self {
+        Lowering {
+            source,
+            defs: source.defs.iter().map(|def| (&def.name[..], def)).collect(),
+            tokens: Vec::new(),
+            types: HashMap::new(),
+            inferring: HashSet::new(),
+            params: Vec::new(),
+            issues: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, offset: usize, message: impl Into<String>) {
+        self.issues.push(Issue {
+            offset,
+            message: message.into(),
+        });
+    }
+
+    fn token(&mut self, sym: &Sym, offset: usize) -> Term {
+        let (name, desc) = match sym {
+            Sym::Literal(lit) => (lit.clone(), TokDesc::Token(lit.clone())),
+            Sym::Regex(re) => (re.clone(), TokDesc::Regex(format!("\\A{re}"))),
+            Sym::Token(name, re) => (name.clone(), TokDesc::Regex(format!("\\A{re}"))),
+            _ => unreachable!(),
+        };
+
+        if name.contains('"') || name.ends_with('\\') {
+            self.report(
+                offset,
+                format!("terminal {name:?} cannot be written as a .l1l literal"),
+            );
+        }
+
+        let term = Term(name);
+
+        if !self.tokens.iter().any(|(t, _)| *t == term) {
+            self.tokens.push((term.clone(), desc));
+        }
+
+        term
+    }
+
+    // Macro parameters stand for their own name while a macro body is
+    // inferred, so the result can be substituted like a declared type.
+    fn def_type(&mut self, name: &str) -> Option<String> {
+        if let Some(ty) = self.types.get(name) {
+            return ty.clone();
+        }
+
+        let def = *self.defs.get(name)?;
+
+        if let Some(ty) = &def.ty {
+            return Some(ty.clone());
+        }
+
+        if !self.inferring.insert(name.to_owned()) {
+            return None;
+        }
+
+        let params = std::mem::replace(&mut self.params, def.params.clone());
+        let ty = def
+            .alts
+            .iter()
+            .find(|alt| matches!(alt.action, Action::Value))
+            .and_then(|alt| self.value_type(&alt.elems));
+
+        self.params = params;
+        self.inferring.remove(name);
+        self.types.insert(name.to_owned(), ty.clone());
+        ty
+    }
+
+    // The type the desugarer gives a symbol, which is what an action sees.
+    fn sym_type(&mut self, sym: &Sym) -> Option<String> {
+        match sym {
+            Sym::Literal(_) | Sym::Regex(_) | Sym::Token(_, _) => Some(STR.to_owned()),
+            Sym::NonTerm(name) if self.params.contains(name) => Some(name.clone()),
+            Sym::NonTerm(name) => self.def_type(name),
+            Sym::Macro(name, args) => {
+                let def = *self.defs.get(&name[..])?;
+                let mut ty = self.def_type(name)?;
+
+                for (param, arg) in def.params.iter().zip(args) {
+                    let arg = self.sym_type(arg)?;
+                    ty = replace_word(&ty, param, &arg);
+                }
+
+                Some(ty)
+            }
+            Sym::Group(alts) => {
+                let types = alts
+                    .iter()
+                    .map(|elems| {
+                        let types = selected(elems)
+                            .into_iter()
+                            .filter(|elem| !matches!(elem.sym, Sym::Location))
+                            .map(|elem| self.sym_type(&elem.sym))
+                            .collect::<Option<Vec<_>>>()?;
+
+                        Some(tuple(&types))
+                    })
+                    .collect::<Option<Vec<_>>>()?;
+
+                match types.iter().all(|ty| *ty == types[0]) {
+                    true => types.into_iter().next(),
+                    false => Some("()".to_owned()),
+                }
+            }
+            Sym::Repeat(inner, Repeat::Optional) => {
+                Some(format!("Option<{}>", self.sym_type(inner)?))
+            }
+            Sym::Repeat(inner, _) => Some(format!("Vec<{}>", self.sym_type(inner)?)),
+            Sym::Location => Some("usize".to_owned()),
+            Sym::Error => None,
+        }
+    }
+
+    fn value_type(&mut self, elems: &[Elem]) -> Option<String> {
+        let types = selected(elems)
+            .into_iter()
+            .map(|elem| self.sym_type(&elem.sym).map(owned))
+            .collect::<Option<Vec<_>>>()?;
+
+        Some(tuple(&types))
+    }
+
+    fn item(&mut self, sym: &Sym, offset: usize) -> Option<Item> {
+        match sym {
+            Sym::Literal(_) | Sym::Regex(_) | Sym::Token(_, _) => Some(Item::Node(Node::Term(
+                self.token(sym, offset),
+                None,
+                Span::default(),
+            ))),
+            Sym::NonTerm(name) => {
+                match self.defs.get(&name[..]) {
+                    _ if self.params.contains(name) => {}
+                    Some(def) if !def.params.is_empty() => {
+                        self.report(offset, format!("macro {name} used without arguments"));
+                    }
+                    Some(_) => {}
+                    None => self.report(offset, format!("undefined nonterminal {name}")),
+                }
+
+                Some(Item::Node(Node::NonTerm {
+                    node: NonTerm(unreserved(name)),
+                    extract_name: None,
+                    args: None,
+                    span: Span::default(),
+                }))
+            }
+            Sym::Macro(name, args) => {
+                let Some(def) = self.defs.get(&name[..]).copied() else {
+                    self.report(offset, format!("undefined macro {name}"));
+                    return None;
+                };
+
+                if def.params.len() != args.len() {
+                    self.report(
+                        offset,
+                        format!("macro {name} expects {} arguments", def.params.len()),
+                    );
+                }
+
+                Some(Item::Macro {
+                    name: NonTerm(unreserved(name)),
+                    args: args
+                        .iter()
+                        .filter_map(|arg| self.item(arg, offset))
+                        .collect(),
+                    extract_name: None,
+                    span: Span::default(),
+                    instance: None,
+                })
+            }
+            Sym::Group(alts) => Some(Item::Group {
+                alts: alts
+                    .iter()
+                    .map(|elems| {
+                        let any = elems.iter().any(|elem| elem.selected);
+
+                        elems
+                            .iter()
+                            .enumerate()
+                            .filter_map(|(i, elem)| {
+                                let name = (any && elem.selected).then(|| match &elem.name {
+                                    Some(name) => unreserved(name),
+                                    None => format!("__{i}"),
+                                });
+
+                                Some(self.item(&elem.sym, offset)?.named(name))
+                            })
+                            .collect()
+                    })
+                    .collect(),
+                extract_name: None,
+                span: Span::default(),
+            }),
+            Sym::Repeat(inner, repeat) => Some(Item::Repeat {
+                item: Box::new(self.item(inner, offset)?),
+                repeat: *repeat,
+                extract_name: None,
+                span: Span::default(),
+            }),
+            Sym::Location => {
+                self.report(offset, "@L and @R have no equivalent, using 0");
+                None
+            }
+            Sym::Error => {
+                self.report(
+                    offset,
+                    "error recovery (!) has no equivalent and was dropped",
+                );
+                None
+            }
+        }
+    }
+
+    fn lower_alt(&mut self, elems: &[Elem], action: &Action, offset: usize) -> Rule {
+        let selected_all = !elems.iter().any(|elem| elem.selected);
+        let mut items = Vec::new();
+        let mut prefix = Vec::new();
+        let mut values = Vec::new();
+
+        for (i, elem) in elems.iter().enumerate() {
+            let is_value = selected_all || elem.selected;
+            let name = match &elem.name {
+                Some(name) => Some(name.clone()),
+                None if is_value
+                    && !matches!(action, Action::Code(code) | Action::Fallible(code) if !code.contains("<>")) =>
+                {
+                    Some(format!("__{i}"))
+                }
+                None => None,
+            };
+
+            if let (Some(name), true) = (&name, is_value) {
+                let ty = self.sym_type(&elem.sym);
+                values.push(match (action, ty) {
+                    (Action::Value, Some(ty)) => to_owned_expr(&ty, name),
+                    _ => name.clone(),
+                });
+            }
+
+            match (&name, &elem.sym) {
+                (Some(name), Sym::Location) => prefix.push(format!("let {name} = 0usize;")),
+                (Some(name), _) if elem.mutable => prefix.push(format!("let mut {name} = {name};")),
+                _ => {}
+            }
+
+            let name = name.map(|name| {
+                let binding = unreserved(&name);
+
+                if binding != name {
+                    prefix.push(format!("let {name} = {binding};"));
+                }
+
+                binding
+            });
+
+            items.extend(self.item(&elem.sym, offset).map(|item| item.named(name)));
+        }
+
+        let value = tuple(&values);
+        let listed = values.join(", ");
+
+        let expand = |code: &str| match code.trim() {
+            "<>" => value.clone(),
+            _ => code.replace("<>", &listed),
+        };
+
+        let code = match action {
+            Action::Value => value.clone(),
+            Action::Code(code) => expand(code),
+            Action::Fallible(code) => {
+                self.report(offset, "fallible action (=>?) was converted to .unwrap()");
+                format!("({}).unwrap()", expand(code))
+            }
+            Action::Location => {
+                self.report(
+                    offset,
+                    "location action (=>@L, =>@R) has no equivalent, using 0",
+                );
+                "0usize".to_owned()
+            }
+        };
+
+        let code = match (prefix.is_empty(), code.contains('\n')) {
+            (true, _) => code,
+            (false, false) => format!("{{ {} {code} }}", prefix.join(" ")),
+            (false, true) => {
+                let body: Vec<_> = prefix
+                    .iter()
+                    .map(|line| &line[..])
+                    .chain(code.lines())
+                    .map(|line| match line {
+                        "" => String::new(),
+                        line => format!("    {line}"),
+                    })
+                    .collect();
+
+                format!("{{\n{}\n}}", body.join("\n"))
+            }
+        };
+
+        Rule::new(items, code, Span::default())
+    }
+
+    fn lower_def(&mut self, def: &Def) -> NonTermDef {
+        let ret_ty = match self.def_type(&def.name) {
+            Some(ty) => ty,
+            None => {
+                self.report(
+                    def.offset,
+                    format!(
+                        "type of {} is not declared and cannot be inferred, using ()",
+                        def.name
+                    ),
+                );
+                "()".to_owned()
+            }
+        };
+
+        if def
+            .ty
+            .as_ref()
+            .is_some_and(|ty| ty.replace("'input", "").contains('\''))
+        {
+            self.report(
+                def.offset,
+                format!("lifetimes in the type of {} are not supported", def.name),
+            );
+        }
+
+        self.params = def.params.clone();
+        let rules = def
+            .alts
+            .iter()
+            .map(|alt| self.lower_alt(&alt.elems, &alt.action, alt.offset))
+            .collect();
+        self.params.clear();
+
+        NonTermDef {
+            name: NonTerm(unreserved(&def.name)),
+            args: None,
+            ret_ty,
+            rules,
+            is_pub: def.is_pub,
+            helper: None,
+            span: Span::default(),
+            name_span: Span::default(),
+        }
+    }
+
+    fn lower(mut self) -> Import {
+        for sym in self.source.tokens.iter() {
+            self.token(sym, 0);
+        }
+
+        let mut nonterms = Vec::new();
+        let mut macros = Vec::new();
+
+        for def in self.source.defs.iter() {
+            let lowered = self.lower_def(def);
+
+            if def.params.is_empty() {
+                nonterms.push(lowered);
+            } else {
+                macros.push(MacroDef {
+                    params: def.params.iter().map(|param| unreserved(param)).collect(),
+                    def: lowered,
+                });
+            }
+        }
+
+        let mut tokens = self.tokens;
+        tokens.sort_by_key(|(_, desc)| match desc {
+            TokDesc::Token(lit) => (0, usize::MAX - lit.len()),
+            TokDesc::Regex(_) => (1, 0),
+        });
+
+        let mut issues = self.source.issues.clone();
+        issues.extend(self.issues);
+        issues.sort_by_key(|issue| issue.offset);
+        issues.dedup_by(|a, b| a.offset == b.offset && a.message == b.message);
+
+        Import {
+            language: Language {
+                preamble: self.source.preamble.join("\n"),
+                lexer: Tokens {
+                    mapping: tokens
+                        .into_iter()
+                        .map(|(term, desc)| (term, desc, Span::default()))
+                        .collect(),
+                    span: Span::default(),
+                },
+                grammar: Grammar {
+                    nonterms,
+                    macros,
+                    span: Span::default(),
+                },
+                tests: None,
+            },
+            issues,
+        }
+    }
+}
+
+fn owned(ty: String) -> String {
+    if ty == STR {
+        "String".to_owned()
+    } else {
+        ty
+    }
+}
+
+fn to_owned_expr(ty: &str, expr: &str) -> String {
+    if ty == STR {
+        format!("{expr}.to_owned()")
+    } else {
+        expr.to_owned()
+    }
+}
+
+fn tuple(parts: &[String]) -> String {
+    match parts {
+        [one] => one.clone(),
+        parts => format!("({})", parts.join(", ")),
+    }
+}
+
+fn selected(elems: &[Elem]) -> Vec<&Elem> {
+    let values = elems.iter().filter(|elem| !matches!(elem.sym, Sym::Error));
+
+    if elems.iter().any(|elem| elem.selected) {
+        values.filter(|elem| elem.selected).collect()
+    } else {
+        values.collect()
+    }
+}
+
+pub fn lower(source: &Source) -> Import {
+    Lowering::new(source).lower()
+}
@@ -0,0 +1,574 @@
+use std::{collections::HashMap, fmt};
+
+use regex::Regex;
+
+use crate::{
+    ast::{predict, FirstSet, Grammar, Language, Node, NonTerm, Span},
+    lexer::{show, Term, TokDesc, Tokens},
+};
+
+#[derive(Debug, Clone)]
+pub enum ParseTree {
+    Node {
+        nonterm: NonTerm,
+        rule: usize,
+        children: Vec<ParseTree>,
+    },
+    Leaf {
+        term: Term,
+        text: String,
+        span: Span,
+    },
+}
+
+impl ParseTree {
+    fn write(&self, f: &mut fmt::Formatter<'_>, depth: usize) -> fmt::Result {
+        let indent = "  ".repeat(depth);
+
+        match self {
+            ParseTree::Node {
+                nonterm, children, ..
+            } => {
+                writeln!(f, "{indent}{}", nonterm.0)?;
+
+                for child in children {
+                    child.write(f, depth + 1)?;
+                }
+
+                Ok(())
+            }
+            ParseTree::Leaf { term, text, .. } => writeln!(f, "{indent}\"{}\" {text:?}", term.0),
+        }
+    }
+}
+
+impl fmt::Display for ParseTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write(f, 0)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum InterpretError {
+    UnknownNonTerm(NonTerm),
+    LeftRecursive(NonTerm),
+    InvalidRegex {
+        term: Term,
+        message: String,
+    },
+    UnexpectedToken {
+        expected: Term,
+        actual: Option<Term>,
+        position: usize,
+    },
+    NoRuleFound {
+        nonterm: NonTerm,
+        actual: Option<Term>,
+        position: usize,
+    },
+    TrailingInput {
+        position: usize,
+    },
+}
+
+impl InterpretError {
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            InterpretError::UnknownNonTerm(_)
+            | InterpretError::LeftRecursive(_)
+            | InterpretError::InvalidRegex { .. } => None,
+            InterpretError::UnexpectedToken { position, .. }
+            | InterpretError::NoRuleFound { position, .. }
+            | InterpretError::TrailingInput { position } => Some(*position),
+        }
+    }
+}
+
+impl fmt::Display for InterpretError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InterpretError::UnknownNonTerm(nonterm) => {
+                write!(f, "unknown nonterminal {}", nonterm.0)
+            }
+            InterpretError::LeftRecursive(nonterm) => {
+                write!(
+                    f,
+                    "{} is left-recursive and cannot be interpreted",
+                    nonterm.0
+                )
+            }
+            InterpretError::InvalidRegex { term, message } => {
+                write!(f, "invalid regex for token \"{}\": {message}", term.0)
+            }
+            InterpretError::UnexpectedToken {
+                expected, actual, ..
+            } => write!(
+                f,
+                "Unexpected token: Expect \"{}\", but found {}",
+                expected.0,
+                show(actual, "None")
+            ),
+            InterpretError::NoRuleFound {
+                nonterm, actual, ..
+            } => write!(
+                f,
+                "while parsing {}, found no rules for {}",
+                nonterm.0,
+                show(actual, "None")
+            ),
+            InterpretError::TrailingInput { .. } => write!(f, "unexpected input after the end"),
+        }
+    }
+}
+
+enum Matcher {
+    Literal(String),
+    Regex(Regex),
+}
+
+pub struct Scanner {
+    matchers: Vec<(Term, Matcher)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lexeme {
+    pub term: Term,
+    pub text: String,
+    pub span: Span,
+}
+
+impl Scanner {
+    pub fn new(tokens: &Tokens) -> Result<Scanner, InterpretError> {
+        let matchers = tokens
+            .mapping
+            .iter()
+            .map(|(term, desc, _)| {
+                let matcher = match desc {
+                    TokDesc::Token(lit) => Matcher::Literal(lit.clone()),
+                    TokDesc::Regex(re) => Matcher::Regex(Regex::new(re).map_err(|e| {
+                        InterpretError::InvalidRegex {
+                            term: term.clone(),
+                            message: e.to_string(),
+                        }
+                    })?),
+                };
+
+                Ok((term.clone(), matcher))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Scanner { matchers })
+    }
+
+    fn skip_spaces(input: &str, position: usize) -> usize {
+        input.len() - input[position..].trim_start().len()
+    }
+
+    fn match_len(matcher: &Matcher, rest: &str) -> Option<usize> {
+        match matcher {
+            Matcher::Literal(lit) => rest.starts_with(&lit[..]).then_some(lit.len()),
+            Matcher::Regex(re) => re.find(rest).filter(|m| m.start() == 0).map(|m| m.end()),
+        }
+    }
+
+    pub fn peek(&self, input: &str, position: usize) -> Option<&Term> {
+        let rest = &input[Self::skip_spaces(input, position)..];
+
+        self.matchers
+            .iter()
+            .find(|(_, matcher)| Self::match_len(matcher, rest).is_some())
+            .map(|(term, _)| term)
+    }
+
+    pub fn expect(&self, term: &Term, input: &str, position: usize) -> Option<Span> {
+        let start = Self::skip_spaces(input, position);
+        let (_, matcher) = self.matchers.iter().find(|(t, _)| t == term)?;
+
+        Self::match_len(matcher, &input[start..]).map(|len| Span {
+            start,
+            end: start + len,
+        })
+    }
+
+    pub fn tokenize(&self, input: &str) -> Result<Vec<Lexeme>, usize> {
+        let mut lexemes = Vec::new();
+        let mut position = Self::skip_spaces(input, 0);
+
+        while position < input.len() {
+            let term = self.peek(input, position).ok_or(position)?;
+            let span = self.expect(term, input, position).ok_or(position)?;
+
+            if span.end == span.start {
+                return Err(position);
+            }
+
+            lexemes.push(Lexeme {
+                term: term.clone(),
+                text: input[span.start..span.end].to_owned(),
+                span,
+            });
+            position = Self::skip_spaces(input, span.end);
+        }
+
+        Ok(lexemes)
+    }
+}
+
+pub struct Interpreter<'l> {
+    grammar: &'l Grammar,
+    start: NonTerm,
+    scanner: Scanner,
+    predict: HashMap<NonTerm, Vec<FirstSet>>,
+}
+
+struct Frame<'l> {
+    nonterm: &'l NonTerm,
+    rule: usize,
+    nodes: &'l [Node],
+    children: Vec<ParseTree>,
+}
+
+impl<'l> Interpreter<'l> {
+    pub fn new(language: &'l Language, start: &str) -> Result<Self, InterpretError> {
+        let start = NonTerm(start.to_owned());
+
+        if !language
+            .grammar
+            .nonterms
+            .iter()
+            .any(|def| def.name == start)
+        {
+            return Err(InterpretError::UnknownNonTerm(start));
+        }
+
+        let entry = Grammar {
+            nonterms: language
+                .grammar
+                .nonterms
+                .iter()
+                .map(|def| {
+                    let mut def = def.clone();
+                    def.is_pub |= def.name == start;
+                    def
+                })
+                .collect(),
+            macros: Vec::new(),
+            span: language.grammar.span,
+        };
+
+        let left_recursive = language.grammar.left_recursive();
+
+        if let Some(def) = language
+            .grammar
+            .nonterms
+            .iter()
+            .find(|def| left_recursive.contains(&def.name))
+        {
+            return Err(InterpretError::LeftRecursive(def.owner().clone()));
+        }
+
+        let fst = entry.build_first();
+        let flw = entry.build_follow(&fst);
+        let predict = entry
+            .nonterms
+            .iter()
+            .map(|def| {
+                let sets = def
+                    .rules
+                    .iter()
+                    .map(|rule| predict(&def.name, &rule.nodes, &fst, &flw))
+                    .collect();
+
+                (def.name.clone(), sets)
+            })
+            .collect();
+
+        Ok(Interpreter {
+            grammar: &language.grammar,
+            start,
+            scanner: Scanner::new(&language.lexer)?,
+            predict,
+        })
+    }
+
+    pub fn scanner(&self) -> &Scanner {
+        &self.scanner
+    }
+
+    fn enter(
+        &self,
+        nonterm: &'l NonTerm,
+        input: &str,
+        position: usize,
+    ) -> Result<Frame<'l>, InterpretError> {
+        let def = self
+            .grammar
+            .nonterms
+            .iter()
+            .find(|def| &def.name == nonterm)
+            .ok_or_else(|| InterpretError::UnknownNonTerm(nonterm.clone()))?;
+
+        let actual = self.scanner.peek(input, position).cloned();
+        let rule = self.predict[nonterm]
+            .iter()
+            .position(|set| set.contains(&actual))
+            .ok_or_else(|| InterpretError::NoRuleFound {
+                nonterm: nonterm.clone(),
+                actual,
+                position: Scanner::skip_spaces(input, position),
+            })?;
+
+        Ok(Frame {
+            nonterm,
+            rule,
+            nodes: &def.rules[rule].nodes,
+            children: Vec::new(),
+        })
+    }
+
+    pub fn parse(&self, input: &str) -> Result<ParseTree, InterpretError> {
+        let start = self
+            .grammar
+            .nonterms
+            .iter()
+            .map(|def| &def.name)
+            .find(|name| **name == self.start)
+            .ok_or_else(|| InterpretError::UnknownNonTerm(self.start.clone()))?;
+
+        let mut position = 0;
+        let mut stack = vec![self.enter(start, input, position)?];
+
+        loop {
+            let top = stack.last_mut().unwrap();
+
+            let Some((node, rest)) = top.nodes.split_first() else {
+                let frame = stack.pop().unwrap();
+                let tree = ParseTree::Node {
+                    nonterm: frame.nonterm.clone(),
+                    rule: frame.rule,
+                    children: frame.children,
+                };
+
+                match stack.last_mut() {
+                    Some(parent) => {
+                        parent.children.push(tree);
+                        continue;
+                    }
+                    None => {
+                        let end = Scanner::skip_spaces(input, position);
+
+                        return if end == input.len() {
+                            Ok(tree)
+                        } else {
+                            Err(InterpretError::TrailingInput { position: end })
+                        };
+                    }
+                }
+            };
+
+            top.nodes = rest;
+
+            match node {
+                Node::Term(term, _, _) => match self.scanner.expect(term, input, position) {
+                    Some(span) => {
+                        top.children.push(ParseTree::Leaf {
+                            term: term.clone(),
+                            text: input[span.start..span.end].to_owned(),
+                            span,
+                        });
+                        position = span.end;
+                    }
+                    None => {
+                        return Err(InterpretError::UnexpectedToken {
+                            expected: term.clone(),
+                            actual: self.scanner.peek(input, position).cloned(),
+                            position: Scanner::skip_spaces(input, position),
+                        })
+                    }
+                },
+                Node::NonTerm { node, .. } => {
+                    let frame = self.enter(node, input, position)?;
+                    stack.push(frame);
+                }
+            }
+        }
+    }
+}
+
+impl Language {
+    pub fn interpret(&self, start: &str, input: &str) -> Result<ParseTree, InterpretError> {
+        Interpreter::new(self, start)?.parse(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{InterpretError, Interpreter};
+    use crate::{ast::Language, parse_language};
+
+    fn language() -> Language {
+        parse_language(
+            r#"
+preamble = !{ }!
+
+tokens = {
+    "lp" = token "(",
+    "rp" = token ")",
+    "+" = token "+",
+    "n" = regex "[0-9]+",
+}
+
+rules = {
+    atom: !{ () }! = {
+        "lp" sum "rp" => !{ () }!,
+        "n" => !{ () }!,
+    }
+
+    pub sum: !{ () }! = {
+        atom ("+" atom)* => !{ () }!,
+    }
+}
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parses_through_helpers() {
+        let language = language();
+        let tree = language.interpret("sum", "1 + (2)").unwrap();
+
+        assert_eq!(
+            tree.to_string(),
+            r#"sum
+  atom
+    "n" "1"
+  sum_group_star
+    sum_group
+      "+" "+"
+      atom
+        "lp" "("
+        sum
+          atom
+            "n" "2"
+          sum_group_star
+        "rp" ")"
+    sum_group_star
+"#
+        );
+    }
+
+    #[test]
+    fn starts_at_any_nonterminal() {
+        let language = language();
+        let interpreter = Interpreter::new(&language, "atom").unwrap();
+
+        assert!(interpreter.parse("(1 + 2)").is_ok());
+        assert!(matches!(
+            interpreter.parse("1 + 2"),
+            Err(InterpretError::TrailingInput { position: 2 })
+        ));
+        assert_eq!(
+            Interpreter::new(&language, "missing")
+                .err()
+                .unwrap()
+                .to_string(),
+            "unknown nonterminal missing"
+        );
+    }
+
+    #[test]
+    fn reports_errors_with_positions() {
+        let language = language();
+        let interpreter = Interpreter::new(&language, "sum").unwrap();
+        let errors: Vec<_> = ["(1", "1)", "1 +", "x"]
+            .into_iter()
+            .map(|input| {
+                let error = interpreter.parse(input).unwrap_err();
+                (error.position(), error.to_string())
+            })
+            .collect();
+
+        assert_eq!(
+            errors,
+            [
+                (
+                    Some(2),
+                    r#"Unexpected token: Expect "rp", but found None"#.to_owned()
+                ),
+                (Some(1), "unexpected input after the end".to_owned()),
+                (
+                    Some(3),
+                    "while parsing atom, found no rules for None".to_owned()
+                ),
+                (
+                    Some(0),
+                    "while parsing sum, found no rules for None".to_owned()
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn tokenizes_input() {
+        let language = language();
+        let interpreter = Interpreter::new(&language, "sum").unwrap();
+        let scanner = interpreter.scanner();
+        let lexemes: Vec<_> = scanner
+            .tokenize(" 12 + (3)")
+            .unwrap()
+            .into_iter()
+            .map(|lexeme| (lexeme.term.0, lexeme.text, lexeme.span.start))
+            .collect();
+
+        assert_eq!(
+            lexemes,
+            [
+                ("n".to_owned(), "12".to_owned(), 1),
+                ("+".to_owned(), "+".to_owned(), 4),
+                ("lp".to_owned(), "(".to_owned(), 6),
+                ("n".to_owned(), "3".to_owned(), 7),
+                ("rp".to_owned(), ")".to_owned(), 8),
+            ]
+        );
+        assert_eq!(scanner.tokenize("1 ?").unwrap_err(), 2);
+    }
+
+    #[test]
+    fn refuses_left_recursion() {
+        let language = parse_language(
+            r#"
+preamble = !{ }!
+
+tokens = {
+    "+" = token "+",
+    "n" = regex "[0-9]+",
+}
+
+rules = {
+    pub sum: !{ () }! = {
+        sum "+" "n" => !{ () }!,
+        "n" => !{ () }!,
+    }
+
+    pub list: !{ () }! = {
+        ("n"?)* => !{ () }!,
+    }
+}
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Interpreter::new(&language, "sum")
+                .err()
+                .unwrap()
+                .to_string(),
+            "sum is left-recursive and cannot be interpreted"
+        );
+        assert!(matches!(
+            language.interpret("list", "1 2"),
+            Err(InterpretError::LeftRecursive(_))
+        ));
+    }
+}
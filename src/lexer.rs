@@ -6,15 +6,31 @@ use std::{
 use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 
+use crate::ast;
+
 #[derive(Debug, Clone)]
 pub enum TokDesc {
     Token(String),
     Regex(String),
 }
 
+pub fn desc(desc: &TokDesc) -> String {
+    match desc {
+        TokDesc::Token(lit) => format!("token \"{lit}\""),
+        TokDesc::Regex(re) => format!("regex \"{}\"", re.strip_prefix("\\A").unwrap_or(re)),
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct Term(pub String);
 
+pub fn show(term: &Option<Term>, none: &str) -> String {
+    match term {
+        Some(term) => format!("\"{}\"", term.0),
+        None => none.to_owned(),
+    }
+}
+
 impl Term {
     pub fn token_name(&self) -> String {
         let hash = {
@@ -48,7 +64,8 @@ impl ToTokens for Term {
 
 #[derive(Debug, Clone)]
 pub struct Tokens {
-    pub mapping: Vec<(Term, TokDesc)>,
+    pub mapping: Vec<(Term, TokDesc, ast::Span)>,
+    pub span: ast::Span,
 }
 
 impl Tokens {
@@ -78,7 +95,7 @@ impl Tokens {
     }
 
     fn token_parsers(&self) -> TokenStream {
-        let res = self.mapping.iter().filter_map(|(tok, tok_def)| {
+        let res = self.mapping.iter().filter_map(|(tok, tok_def, _)| {
             let re_name = tok.re_name();
 
             if let TokDesc::Regex(re) = tok_def {
@@ -90,7 +107,7 @@ impl Tokens {
             }
         });
 
-        let fns = self.mapping.iter().map(|(tok, tok_def)| {
+        let fns = self.mapping.iter().map(|(tok, tok_def, _)| {
             let re_name = tok.re_name();
             let parse_fn = tok.parse_fn();
             let parse_body = match tok_def {
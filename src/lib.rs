@@ -1,3 +1,40 @@
+use proc_macro2::TokenStream;
+
 pub mod ast;
+pub mod build;
+pub mod coverage;
+pub mod diagnostics;
+pub mod diff;
+pub mod export;
+pub mod import;
+pub mod interpret;
 pub mod lexer;
 pub mod notation;
+pub mod sample;
+pub mod stats;
+
+use ast::Language;
+use diagnostics::{Diagnostic, Diagnostics};
+
+pub fn parse_language(source: &str) -> Result<Language, Diagnostics> {
+    let lexer = notation::lexer::Lexer::new(source);
+    let parser = notation::grammar::DescriptionParser::new();
+
+    let mut language = parser
+        .parse(lexer)
+        .map_err(|e| Diagnostics::from(Diagnostic::from(e)))?;
+
+    notation::desugar::desugar(&mut language)?;
+    Ok(language)
+}
+
+pub fn format(source: &str) -> Result<String, Diagnostics> {
+    let language = parse_language(source)?;
+    Ok(notation::format::format(&language, source))
+}
+
+pub fn compile(source: &str) -> Result<TokenStream, Diagnostics> {
+    let language = parse_language(source)?;
+    language.check()?;
+    Ok(language.generate())
+}
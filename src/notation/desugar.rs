@@ -0,0 +1,977 @@
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+};
+
+use proc_macro2::TokenStream;
+
+use crate::{
+    ast::{
+        Display, Item, Language, MacroDef, Node, NonTerm, NonTermDef, Origin, Repeat, Rule, Span,
+    },
+    diagnostics::{Diagnostic, Diagnostics},
+    lexer::Term,
+};
+
+const MAX_EXPANSION_DEPTH: usize = 32;
+
+struct Helpers {
+    types: HashMap<NonTerm, String>,
+    names: HashSet<String>,
+    made: HashMap<(String, Repeat), NonTerm>,
+    macros: HashMap<NonTerm, MacroDef>,
+    expanded: HashMap<String, NonTerm>,
+    instances: HashMap<NonTerm, NonTerm>,
+    depth: usize,
+    defs: Vec<NonTermDef>,
+    diagnostics: Diagnostics,
+}
+
+fn key(node: &Node) -> String {
+    match node {
+        Node::Term(term, _, _) => format!("\"{}\"", term.0),
+        Node::NonTerm { node, args, .. } => match args {
+            Some(args) => format!("{}({args})", node.0),
+            None => node.0.clone(),
+        },
+    }
+}
+
+fn base(node: &Node) -> String {
+    match node {
+        Node::NonTerm { node, .. } => node.0.clone(),
+        Node::Term(Term(term), _, _) => {
+            let ident = term.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+                && term.starts_with(|c: char| c.is_ascii_alphabetic());
+
+            if ident {
+                term.to_lowercase()
+            } else {
+                "token".to_owned()
+            }
+        }
+    }
+}
+
+fn name(node: &Node) -> Option<&String> {
+    match node {
+        Node::NonTerm { extract_name, .. } | Node::Term(_, extract_name, _) => {
+            extract_name.as_ref()
+        }
+    }
+}
+
+fn normalize(ty: &str) -> String {
+    TokenStream::from_str(ty).map_or_else(|_| ty.trim().to_owned(), |ty| ty.to_string())
+}
+
+fn tuple(parts: &[String]) -> String {
+    match parts {
+        [one] => one.clone(),
+        parts => format!("({})", parts.join(", ")),
+    }
+}
+
+// Replaces whole identifiers in Rust code, leaving lifetimes such as `'T` alone.
+fn substitute(code: &str, types: &HashMap<&str, String>) -> String {
+    let mut out = String::new();
+    let mut chars = code.char_indices().peekable();
+
+    while let Some((start, ch)) = chars.next() {
+        if !(ch.is_alphabetic() || ch == '_') {
+            out.push(ch);
+            continue;
+        }
+
+        let mut end = start + ch.len_utf8();
+
+        while let Some(&(i, ch)) = chars.peek() {
+            if !(ch.is_alphanumeric() || ch == '_') {
+                break;
+            }
+
+            end = i + ch.len_utf8();
+            chars.next();
+        }
+
+        let word = &code[start..end];
+
+        match types.get(word) {
+            Some(ty) if !out.ends_with('\'') => out.push_str(ty),
+            _ => out.push_str(word),
+        }
+    }
+
+    out
+}
+
+fn call(helper: &NonTerm, name: Option<String>, args: Option<String>, span: Span) -> Node {
+    Node::NonTerm {
+        node: helper.clone(),
+        extract_name: name,
+        args,
+        span,
+    }
+}
+
+// Copies the instances recorded while lowering a bound macro body onto the
+// copy kept as written, whose parameters hold the arguments as written.
+fn fill(written: &mut Item, lowered: &Item) {
+    match (written, lowered) {
+        (Item::Group { alts, .. }, Item::Group { alts: lowered, .. }) => {
+            for (item, lowered) in alts.iter_mut().flatten().zip(lowered.iter().flatten()) {
+                fill(item, lowered);
+            }
+        }
+        (Item::Repeat { item, .. }, Item::Repeat { item: lowered, .. }) => fill(item, lowered),
+        (
+            Item::Macro { args, instance, .. },
+            Item::Macro {
+                args: lowered,
+                instance: made,
+                ..
+            },
+        ) => {
+            instance.clone_from(made);
+
+            for (item, lowered) in args.iter_mut().zip(lowered.iter()) {
+                fill(item, lowered);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Helpers {
+    fn error(&mut self, span: Span, message: String) {
+        let seen = self.diagnostics.iter().any(|diagnostic| {
+            matches!(diagnostic, Diagnostic::Syntax { span: s, message: m } if *s == span && *m == message)
+        });
+
+        if !seen {
+            self.diagnostics.push(Diagnostic::Syntax { span, message });
+        }
+    }
+
+    fn fresh(&mut self, name: String) -> NonTerm {
+        let mut fresh = name.clone();
+        let mut n = 1;
+
+        while !self.names.insert(fresh.clone()) {
+            n += 1;
+            fresh = format!("{name}_{n}");
+        }
+
+        NonTerm(fresh)
+    }
+
+    fn ty(&self, node: &Node) -> String {
+        match node {
+            Node::Term(..) => "&'input str".to_owned(),
+            Node::NonTerm { node, .. } => self
+                .types
+                .get(node)
+                .cloned()
+                .unwrap_or_else(|| "()".to_owned()),
+        }
+    }
+
+    // Helpers made while expanding a macro belong to the macro, not its instance.
+    fn origin(&self, owner: &NonTerm, item: &Item) -> Origin {
+        Origin {
+            nonterm: self.instances.get(owner).unwrap_or(owner).clone(),
+            item: Display(item).to_string(),
+        }
+    }
+
+    fn push(&mut self, def: NonTermDef) {
+        self.types.insert(def.name.clone(), def.ret_ty.clone());
+        self.defs.push(def);
+    }
+
+    fn repeat(
+        &mut self,
+        origin: Origin,
+        inner: &Item,
+        node: &Node,
+        repeat: Repeat,
+        rule_span: Span,
+        span: Span,
+    ) -> NonTerm {
+        if let Some(name) = self.made.get(&(key(node), repeat)) {
+            return name.clone();
+        }
+
+        let ty = self.ty(node);
+        let star = match repeat {
+            Repeat::Plus => {
+                Some(self.repeat(origin.clone(), inner, node, Repeat::Star, rule_span, span))
+            }
+            _ => None,
+        };
+
+        let suffix = match repeat {
+            Repeat::Optional => "opt",
+            Repeat::Star => "star",
+            Repeat::Plus => "plus",
+        };
+        let name = self.fresh(format!("{}_{suffix}", base(node)));
+        let item = node.clone().named(Some("v".to_owned()));
+        let again = Item::Repeat {
+            item: Box::new(inner.clone()),
+            repeat: Repeat::Star,
+            extract_name: None,
+            span,
+        };
+        let rule = |nodes: Vec<Node>, items: Vec<Item>, code: &str| Rule {
+            nodes,
+            items: Some(items),
+            code: code.to_owned(),
+            span: rule_span,
+        };
+
+        let (args, ret_ty, rules) = match repeat {
+            Repeat::Optional => (
+                None,
+                format!("Option<{ty}>"),
+                vec![
+                    rule(vec![item], vec![inner.clone()], "Some(v)"),
+                    rule(vec![], vec![], "None"),
+                ],
+            ),
+            Repeat::Star => {
+                let rest = call(
+                    &name,
+                    Some("rest".to_owned()),
+                    Some("{ acc.push(v); acc }".to_owned()),
+                    span,
+                );
+
+                (
+                    Some(format!("mut acc: Vec<{ty}>")),
+                    format!("Vec<{ty}>"),
+                    vec![
+                        rule(vec![item, rest], vec![inner.clone(), again], "rest"),
+                        rule(vec![], vec![], "acc"),
+                    ],
+                )
+            }
+            Repeat::Plus => {
+                let star = star.expect("plus helpers are built on star helpers");
+                let rest = call(
+                    &star,
+                    Some("rest".to_owned()),
+                    Some("vec![v]".to_owned()),
+                    span,
+                );
+
+                (
+                    None,
+                    format!("Vec<{ty}>"),
+                    vec![rule(vec![item, rest], vec![inner.clone(), again], "rest")],
+                )
+            }
+        };
+
+        self.push(NonTermDef {
+            name: name.clone(),
+            args,
+            ret_ty,
+            rules,
+            is_pub: false,
+            helper: Some(origin),
+            span,
+            name_span: span,
+        });
+        self.made.insert((key(node), repeat), name.clone());
+        name
+    }
+
+    // The value of an alternative in a group is its bound symbols, or all of
+    // its symbols when none is bound, as a tuple unless there is exactly one.
+    fn group(
+        &mut self,
+        owner: &NonTerm,
+        origin: Origin,
+        alts: &mut [Vec<Item>],
+        bound: bool,
+        rule_span: Span,
+        span: Span,
+    ) -> NonTerm {
+        let mut rules = Vec::new();
+        let mut types = Vec::new();
+
+        for alt in alts.iter_mut() {
+            let named = alt.iter().any(|item| item.extract_name().is_some());
+            let mut nodes: Vec<_> = alt
+                .iter_mut()
+                .map(|item| {
+                    let used = bound && (!named || item.extract_name().is_some());
+                    self.lower(owner, item, true, used, rule_span)
+                })
+                .collect();
+            let mut values = Vec::new();
+            let mut tys = Vec::new();
+
+            for (i, node) in nodes.iter_mut().enumerate() {
+                if name(node).is_none() {
+                    if named {
+                        continue;
+                    }
+
+                    *node = node.clone().named(Some(format!("__{i}")));
+                }
+
+                values.push(name(node).cloned().unwrap_or_default());
+                tys.push(self.ty(node));
+            }
+
+            types.push(tuple(&tys));
+            rules.push((nodes, alt.clone(), tuple(&values)));
+        }
+
+        let agree = types.iter().all(|ty| normalize(ty) == normalize(&types[0]));
+
+        if !agree && bound {
+            self.error(
+                span,
+                format!(
+                    "alternatives of a bound group must have the same type, found {}",
+                    types
+                        .iter()
+                        .map(|ty| format!("`{}`", ty.trim()))
+                        .collect::<Vec<_>>()
+                        .join(" and ")
+                ),
+            );
+        }
+
+        let name = self.fresh(format!("{}_group", owner.0));
+        let ret_ty = if agree {
+            types[0].clone()
+        } else {
+            "()".to_owned()
+        };
+
+        self.push(NonTermDef {
+            name: name.clone(),
+            args: None,
+            ret_ty,
+            rules: rules
+                .into_iter()
+                .map(|(nodes, items, code)| Rule {
+                    nodes,
+                    items: Some(items),
+                    code: if agree { code } else { "()".to_owned() },
+                    span: rule_span,
+                })
+                .collect(),
+            is_pub: false,
+            helper: Some(origin),
+            span,
+            name_span: span,
+        });
+        name
+    }
+
+    fn bind(&mut self, item: &Item, args: &HashMap<&str, Item>) -> Item {
+        match item {
+            Item::Node(Node::NonTerm {
+                node,
+                extract_name,
+                args: code,
+                span,
+            }) => match args.get(&node.0[..]) {
+                Some(arg) => {
+                    if code.is_some() {
+                        self.error(
+                            *span,
+                            format!("macro parameter {} cannot take arguments", node.0),
+                        );
+                    }
+
+                    arg.clone().named(extract_name.clone())
+                }
+                None => item.clone(),
+            },
+            Item::Node(Node::Term(..)) => item.clone(),
+            Item::Group {
+                alts,
+                extract_name,
+                span,
+            } => Item::Group {
+                alts: alts
+                    .iter()
+                    .map(|alt| alt.iter().map(|item| self.bind(item, args)).collect())
+                    .collect(),
+                extract_name: extract_name.clone(),
+                span: *span,
+            },
+            Item::Repeat {
+                item,
+                repeat,
+                extract_name,
+                span,
+            } => Item::Repeat {
+                item: Box::new(self.bind(item, args)),
+                repeat: *repeat,
+                extract_name: extract_name.clone(),
+                span: *span,
+            },
+            Item::Macro {
+                name,
+                args: inner,
+                extract_name,
+                span,
+                instance,
+            } => Item::Macro {
+                name: name.clone(),
+                args: inner.iter().map(|item| self.bind(item, args)).collect(),
+                extract_name: extract_name.clone(),
+                span: *span,
+                instance: instance.clone(),
+            },
+        }
+    }
+
+    fn expand(
+        &mut self,
+        origin: Origin,
+        name: &NonTerm,
+        args: &[Node],
+        written: &[Item],
+        span: Span,
+    ) -> Option<NonTerm> {
+        let Some(m) = self.macros.get(name).cloned() else {
+            self.error(span, format!("undefined macro {}", name.0));
+            return None;
+        };
+
+        if args.len() != m.params.len() {
+            self.error(
+                span,
+                format!(
+                    "macro {} takes {} arguments, found {}",
+                    name.0,
+                    m.params.len(),
+                    args.len()
+                ),
+            );
+            return None;
+        }
+
+        let key = format!(
+            "{}<{}>",
+            name.0,
+            args.iter().map(key).collect::<Vec<_>>().join(", ")
+        );
+
+        if let Some(expanded) = self.expanded.get(&key) {
+            return Some(expanded.clone());
+        }
+
+        if self.depth >= MAX_EXPANSION_DEPTH {
+            self.error(
+                span,
+                format!("expansion of macro {} does not terminate", name.0),
+            );
+            return None;
+        }
+
+        let mangled = self.fresh(format!(
+            "{}_{}",
+            name.0,
+            args.iter().map(base).collect::<Vec<_>>().join("_")
+        ));
+        let params: HashMap<&str, Item> = m
+            .params
+            .iter()
+            .map(String::as_str)
+            .zip(args.iter().cloned().map(Item::Node))
+            .collect();
+        let written: HashMap<&str, Item> = m
+            .params
+            .iter()
+            .map(String::as_str)
+            .zip(written.iter().cloned())
+            .collect();
+        let types: HashMap<&str, String> = m
+            .params
+            .iter()
+            .map(String::as_str)
+            .zip(args)
+            .map(|(param, node)| (param, self.ty(node)))
+            .collect();
+        let ret_ty = substitute(&m.def.ret_ty, &types);
+
+        self.expanded.insert(key, mangled.clone());
+        self.instances.insert(mangled.clone(), name.clone());
+        self.types.insert(mangled.clone(), ret_ty.clone());
+        self.depth += 1;
+
+        let rules = m
+            .def
+            .rules
+            .iter()
+            .map(|rule| {
+                let items: Vec<_> = match &rule.items {
+                    Some(items) => items.clone(),
+                    None => rule.nodes.iter().cloned().map(Item::Node).collect(),
+                };
+                let mut shown: Vec<_> =
+                    items.iter().map(|item| self.bind(item, &written)).collect();
+                let nodes = items
+                    .iter()
+                    .zip(shown.iter_mut())
+                    .map(|(item, shown)| {
+                        let mut item = self.bind(item, &params);
+                        let bound = item.extract_name().is_some();
+                        let node = self.lower(&mangled, &mut item, false, bound, rule.span);
+                        fill(shown, &item);
+                        node
+                    })
+                    .collect();
+                let mut rule = Rule::new(shown, rule.code.clone(), rule.span);
+
+                rule.nodes = nodes;
+                rule
+            })
+            .collect();
+
+        self.depth -= 1;
+        self.push(NonTermDef {
+            name: mangled.clone(),
+            args: m.def.args.as_deref().map(|args| substitute(args, &types)),
+            ret_ty,
+            rules,
+            is_pub: false,
+            helper: Some(origin),
+            span: m.def.span,
+            name_span: m.def.name_span,
+        });
+        Some(mangled)
+    }
+
+    fn lower(
+        &mut self,
+        owner: &NonTerm,
+        item: &mut Item,
+        nested: bool,
+        bound: bool,
+        rule_span: Span,
+    ) -> Node {
+        let origin = self.origin(owner, item);
+
+        match item {
+            Item::Node(node) => {
+                if let Node::NonTerm {
+                    node,
+                    args: Some(_),
+                    span,
+                    ..
+                } = node
+                {
+                    if nested {
+                        self.error(
+                            *span,
+                            format!(
+                                "{} cannot take arguments inside a group or a repetition",
+                                node.0
+                            ),
+                        );
+                    }
+                }
+
+                node.clone()
+            }
+            Item::Group {
+                alts,
+                extract_name,
+                span,
+            } => {
+                let helper = self.group(owner, origin, alts, bound, rule_span, *span);
+                call(&helper, extract_name.clone(), None, *span)
+            }
+            Item::Repeat {
+                item,
+                repeat,
+                extract_name,
+                span,
+            } => {
+                let grouped = self.origin(owner, item);
+                let node = match &mut **item {
+                    Item::Group { alts, span, .. } => {
+                        let group = self.group(owner, grouped, alts, bound, rule_span, *span);
+                        call(&group, None, None, *span)
+                    }
+                    item => self.lower(owner, item, true, bound, rule_span),
+                };
+
+                let helper = self.repeat(origin, item, &node, *repeat, rule_span, *span);
+                let args = match repeat {
+                    Repeat::Star => Some("Vec::new()".to_owned()),
+                    Repeat::Optional | Repeat::Plus => None,
+                };
+
+                call(&helper, extract_name.clone(), args, *span)
+            }
+            Item::Macro {
+                name,
+                args,
+                extract_name,
+                span,
+                instance,
+            } => {
+                let nodes: Vec<_> = args
+                    .iter_mut()
+                    .map(|item| self.lower(owner, item, true, true, rule_span))
+                    .collect();
+                let expanded = self.expand(origin, name, &nodes, args, *span);
+
+                instance.clone_from(&expanded);
+                call(
+                    expanded.as_ref().unwrap_or(name),
+                    extract_name.clone(),
+                    None,
+                    *span,
+                )
+            }
+        }
+    }
+}
+
+pub fn desugar(language: &mut Language) -> Result<(), Diagnostics> {
+    let nonterms = &mut language.grammar.nonterms;
+    let mut helpers = Helpers {
+        types: HashMap::new(),
+        names: nonterms.iter().map(|def| def.name.0.clone()).collect(),
+        made: HashMap::new(),
+        macros: HashMap::new(),
+        expanded: HashMap::new(),
+        instances: HashMap::new(),
+        depth: 0,
+        defs: Vec::new(),
+        diagnostics: Diagnostics::default(),
+    };
+
+    for m in language.grammar.macros.iter() {
+        let name = &m.def.name;
+
+        if helpers.names.contains(&name.0) || helpers.macros.contains_key(name) {
+            helpers.diagnostics.push(Diagnostic::DuplicateNonTerm {
+                nonterm: name.clone(),
+                span: m.def.name_span,
+            });
+        }
+
+        helpers.macros.insert(name.clone(), m.clone());
+    }
+
+    for def in nonterms.iter() {
+        helpers
+            .types
+            .entry(def.name.clone())
+            .or_insert_with(|| def.ret_ty.clone());
+    }
+
+    for def in nonterms.iter_mut() {
+        for rule in def.rules.iter_mut() {
+            if let Some(items) = &mut rule.items {
+                rule.nodes = items
+                    .iter_mut()
+                    .map(|item| {
+                        let bound = item.extract_name().is_some();
+                        helpers.lower(&def.name, item, false, bound, rule.span)
+                    })
+                    .collect();
+            }
+        }
+    }
+
+    nonterms.extend(helpers.defs);
+
+    if helpers.diagnostics.is_empty() {
+        Ok(())
+    } else {
+        Err(helpers.diagnostics)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::{normalize, substitute};
+    use crate::{
+        ast::{Display, Item, Language, Node, NonTermDef},
+        diagnostics::Diagnostics,
+        parse_language,
+    };
+
+    fn language(rules: &str) -> Result<Language, Diagnostics> {
+        parse_language(&format!(
+            r#"
+preamble = !{{ }}!
+
+tokens = {{
+    "a" = token "a",
+    "," = token ",",
+    "*" = token "*",
+    "/" = token "/",
+    "n" = regex "[0-9]+",
+}}
+
+rules = {{
+    atom: !{{ i32 }}! = {{
+        <n:"n"> => !{{ n.parse().unwrap() }}!,
+    }}
+{rules}
+}}
+"#
+        ))
+    }
+
+    fn def<'l>(language: &'l Language, name: &str) -> &'l NonTermDef {
+        let def = language
+            .grammar
+            .nonterms
+            .iter()
+            .find(|def| def.name.0 == name);
+        def.unwrap_or_else(|| panic!("{name} is not defined"))
+    }
+
+    fn ty(def: &NonTermDef) -> String {
+        normalize(&def.ret_ty)
+    }
+
+    fn nodes(def: &NonTermDef) -> Vec<String> {
+        def.rules
+            .iter()
+            .map(|rule| Display(&rule.nodes[..]).to_string().trim_end().to_owned())
+            .collect()
+    }
+
+    fn call(node: &Node) -> (&str, Option<&str>, Option<&str>) {
+        match node {
+            Node::NonTerm {
+                node,
+                extract_name,
+                args,
+                ..
+            } => (&node.0, extract_name.as_deref(), args.as_deref()),
+            Node::Term(..) => panic!("expected a nonterminal"),
+        }
+    }
+
+    #[test]
+    fn star() {
+        let language = language(
+            r#"
+    pub top: !{ () }! = {
+        <v:"a"*> "," "a"* => !{ () }!,
+    }
+"#,
+        )
+        .unwrap();
+
+        let star = def(&language, "a_star");
+        assert_eq!(ty(star), normalize("Vec<&'input str>"));
+        assert_eq!(star.args.as_deref(), Some("mut acc: Vec<&'input str>"));
+        assert_eq!(nodes(star), [r#""a" a_star"#, ""]);
+        assert_eq!(star.rules[1].code, "acc");
+
+        let top = &def(&language, "top").rules[0];
+        assert_eq!(
+            call(&top.nodes[0]),
+            ("a_star", Some("v"), Some("Vec::new()"))
+        );
+        assert_eq!(call(&top.nodes[2]), ("a_star", None, Some("Vec::new()")));
+
+        let stars = language.grammar.nonterms.iter();
+        assert_eq!(
+            stars.filter(|def| def.name.0.starts_with("a_star")).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn plus() {
+        let language = language(
+            r#"
+    pub top: !{ Vec<i32> }! = {
+        <v:atom+> => !{ v }!,
+    }
+"#,
+        )
+        .unwrap();
+
+        let plus = def(&language, "atom_plus");
+        assert_eq!(ty(plus), normalize("Vec<i32>"));
+        assert_eq!(plus.args, None);
+        assert_eq!(nodes(plus), ["atom atom_star"]);
+        assert_eq!(
+            call(&plus.rules[0].nodes[1]),
+            ("atom_star", Some("rest"), Some("vec![v]"))
+        );
+        assert_eq!(ty(def(&language, "atom_star")), normalize("Vec<i32>"));
+    }
+
+    #[test]
+    fn optional() {
+        let language = language(
+            r#"
+    pub top: !{ Option<&'input str> }! = {
+        <v:"n"?> => !{ v }!,
+    }
+"#,
+        )
+        .unwrap();
+
+        let opt = def(&language, "n_opt");
+        assert_eq!(ty(opt), normalize("Option<&'input str>"));
+        assert_eq!(nodes(opt), [r#""n""#, ""]);
+        assert_eq!(opt.rules[0].code, "Some(v)");
+        assert_eq!(opt.rules[1].code, "None");
+    }
+
+    #[test]
+    fn nested_groups() {
+        let language = language(
+            r#"
+    pub prod: !{ i32 }! = {
+        <first:atom> <rest:(("*" | "/") atom)*> => !{ first }!,
+    }
+"#,
+        )
+        .unwrap();
+
+        let op = def(&language, "prod_group");
+        assert_eq!(ty(op), normalize("&'input str"));
+        assert_eq!(nodes(op), [r#""*""#, r#""/""#]);
+
+        let pair = def(&language, "prod_group_2");
+        assert_eq!(ty(pair), normalize("(&'input str, i32)"));
+        assert_eq!(nodes(pair), ["prod_group atom"]);
+        assert_eq!(pair.rules[0].code, "(__0, __1)");
+
+        let star = def(&language, "prod_group_2_star");
+        assert_eq!(ty(star), normalize("Vec<(&'input str, i32)>"));
+        assert_eq!(
+            star.rules[0].arm(),
+            r#"(("*" | "/") atom) (("*" | "/") atom)*"#
+        );
+
+        let origin = star.helper.as_ref().unwrap();
+        assert_eq!(origin.nonterm.0, "prod");
+        assert_eq!(origin.item, r#"(("*" | "/") atom)*"#);
+    }
+
+    #[test]
+    fn bound_group_types_must_agree() {
+        let diagnostics = language(
+            r#"
+    pub top: !{ () }! = {
+        <v:("a" | atom)> => !{ () }!,
+    }
+"#,
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            diagnostics.iter().next().unwrap().to_string(),
+            "alternatives of a bound group must have the same type, found `&'input str` and `i32`"
+        );
+    }
+
+    #[test]
+    fn macro_instances() {
+        let language = language(
+            r#"
+    Comma<T>: !{ Vec<T> }! = {
+        <first:T> <rest:("," <v:T>)*> => !{ vec![first] }!,
+    }
+
+    pub top: !{ Vec<i32> }! = {
+        <v:Comma<atom>> Comma<atom> => !{ v }!,
+    }
+"#,
+        )
+        .unwrap();
+
+        let comma = def(&language, "Comma_atom");
+        assert_eq!(ty(comma), normalize("Vec<i32>"));
+        assert_eq!(nodes(comma), ["atom Comma_atom_group_star"]);
+        assert_eq!(comma.rules[0].arm(), r#"atom ("," atom)*"#);
+
+        let origin = comma.helper.as_ref().unwrap();
+        assert_eq!(
+            (&origin.nonterm.0[..], &origin.item[..]),
+            ("top", "Comma<atom>")
+        );
+
+        let origin = def(&language, "Comma_atom_group_star").helper.as_ref();
+        assert_eq!(origin.unwrap().nonterm.0, "Comma");
+
+        let top = &def(&language, "top").rules[0];
+        assert_eq!(nodes(def(&language, "top")), ["Comma_atom Comma_atom"]);
+        assert!(matches!(
+            &top.items.as_ref().unwrap()[0],
+            Item::Macro { instance: Some(instance), .. } if instance.0 == "Comma_atom"
+        ));
+        assert_eq!(language.grammar.written().len(), 3);
+    }
+
+    #[test]
+    fn macro_arity() {
+        let diagnostics = language(
+            r#"
+    Pair<A, B>: !{ (A, B) }! = {
+        <a:A> <b:B> => !{ (a, b) }!,
+    }
+
+    pub top: !{ () }! = {
+        Pair<atom> Missing<atom> => !{ () }!,
+    }
+"#,
+        )
+        .unwrap_err();
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            [
+                "macro Pair takes 2 arguments, found 1",
+                "undefined macro Missing"
+            ]
+        );
+    }
+
+    #[test]
+    fn expansion_depth() {
+        let diagnostics = language(
+            r#"
+    Nest<T>: !{ () }! = {
+        Nest<T?> => !{ () }!,
+    }
+
+    pub top: !{ () }! = {
+        Nest<"a"> => !{ () }!,
+    }
+"#,
+        )
+        .unwrap_err();
+
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(messages, ["expansion of macro Nest does not terminate"]);
+    }
+
+    #[test]
+    fn substitutes_whole_identifiers() {
+        let types = HashMap::from([("T", "i32".to_owned())]);
+
+        assert_eq!(
+            substitute("Vec<T> + 'T + Tx + T::MAX", &types),
+            "Vec<i32> + 'T + Tx + i32::MAX"
+        );
+    }
+}
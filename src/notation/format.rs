@@ -0,0 +1,529 @@
+use logos::Logos;
+
+use crate::{
+    ast::{Expect, Item, Language, Node, NonTermDef, Rule, Span, TestGroup, Tests},
+    lexer::desc,
+    notation::{lexer::comments, tokens::Token},
+};
+
+const INDENT: &str = "    ";
+
+// Splits code at the newlines outside string literals and comments, so that
+// re-indenting a line never changes what a multi-line string contains.
+fn logical_lines(code: &str) -> Vec<&str> {
+    let chars: Vec<(usize, char)> = code.char_indices().collect();
+    let at = |i: usize| chars.get(i).map_or('\0', |&(_, c)| c);
+    let mut lines = Vec::new();
+    let mut start = 0;
+    let mut i = 0;
+
+    while i < chars.len() {
+        match at(i) {
+            '\n' => {
+                lines.push(&code[start..chars[i].0]);
+                start = chars[i].0 + 1;
+            }
+            '/' if at(i + 1) == '/' => {
+                while i + 1 < chars.len() && at(i + 1) != '\n' {
+                    i += 1;
+                }
+            }
+            '/' if at(i + 1) == '*' => {
+                i += 2;
+
+                while i < chars.len() && !(at(i) == '*' && at(i + 1) == '/') {
+                    i += 1;
+                }
+
+                i += 1;
+            }
+            '"' => {
+                i += 1;
+
+                while i < chars.len() && at(i) != '"' {
+                    i += if at(i) == '\\' { 2 } else { 1 };
+                }
+            }
+            'r' if matches!(at(i + 1), '"' | '#') => {
+                let hashes = (i + 1..chars.len()).take_while(|&j| at(j) == '#').count();
+
+                if at(i + 1 + hashes) == '"' {
+                    i += hashes + 2;
+
+                    while i < chars.len()
+                        && !(at(i) == '"' && (1..=hashes).all(|h| at(i + h) == '#'))
+                    {
+                        i += 1;
+                    }
+
+                    i += hashes;
+                }
+            }
+            '\'' if at(i + 1) == '\\' => {
+                i += 2;
+
+                while i < chars.len() && at(i) != '\'' {
+                    i += 1;
+                }
+            }
+            '\'' if at(i + 2) == '\'' => i += 2,
+            _ => {}
+        }
+
+        i += 1;
+    }
+
+    lines.push(&code[start.min(code.len())..]);
+    lines
+}
+
+pub fn code_lines(code: &str) -> Vec<String> {
+    let mut raw = logical_lines(code).into_iter();
+    let first = raw.next().unwrap_or_default().trim();
+    let rest: Vec<_> = raw.map(str::trim_end).collect();
+
+    let dedent = rest
+        .iter()
+        .filter(|line| !line.is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+
+    let mut lines: Vec<String> = std::iter::once(first.to_owned())
+        .chain(
+            rest.iter()
+                .map(|line| line.get(dedent..).unwrap_or_default().to_owned()),
+        )
+        .collect();
+
+    while lines.last().is_some_and(|line| line.is_empty()) {
+        lines.pop();
+    }
+
+    let leading = lines.iter().take_while(|line| line.is_empty()).count();
+    lines.drain(..leading);
+    lines
+}
+
+fn code(code: &str, depth: usize) -> String {
+    match &code_lines(code)[..] {
+        [] => "!{ }!".to_owned(),
+        [line] => format!("!{{ {line} }}!"),
+        lines => {
+            let mut out = "!{\n".to_owned();
+
+            for line in lines {
+                if !line.is_empty() {
+                    out.push_str(&INDENT.repeat(depth + 1));
+                    out.push_str(line);
+                }
+
+                out.push('\n');
+            }
+
+            out.push_str(&INDENT.repeat(depth));
+            out.push_str("}!");
+            out
+        }
+    }
+}
+
+fn literal(text: &str) -> String {
+    format!("\"{text}\"")
+}
+
+fn named(inner: String, name: &Option<String>) -> String {
+    match name {
+        Some(name) => format!("<{name}:{inner}>"),
+        None => inner,
+    }
+}
+
+fn node(node: &Node, depth: usize) -> String {
+    match node {
+        Node::Term(term, name, _) => named(literal(&term.0), name),
+        Node::NonTerm {
+            node,
+            extract_name,
+            args,
+            ..
+        } => {
+            let inner = match args {
+                Some(args) => format!("{}({})", node.0, code(args, depth)),
+                None => node.0.clone(),
+            };
+
+            named(inner, extract_name)
+        }
+    }
+}
+
+fn symbol(item: &Item, depth: usize) -> String {
+    match item {
+        Item::Node(n) => node(n, depth),
+        Item::Group {
+            alts, extract_name, ..
+        } => {
+            let alts: Vec<_> = alts
+                .iter()
+                .map(|alt| {
+                    alt.iter()
+                        .map(|i| symbol(i, depth))
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect();
+
+            named(format!("({})", alts.join(" | ")), extract_name)
+        }
+        Item::Repeat {
+            item: inner,
+            repeat,
+            extract_name,
+            ..
+        } => named(
+            format!("{}{}", symbol(inner, depth), repeat.suffix()),
+            extract_name,
+        ),
+        Item::Macro {
+            name,
+            args,
+            extract_name,
+            ..
+        } => {
+            let args: Vec<_> = args.iter().map(|i| symbol(i, depth)).collect();
+            named(format!("{}<{}>", name.0, args.join(", ")), extract_name)
+        }
+    }
+}
+
+struct Formatter<'s> {
+    source: &'s str,
+    comments: Vec<(Span, String)>,
+    next: usize,
+    out: String,
+}
+
+impl<'s> Formatter<'s> {
+    fn line(&mut self, depth: usize, text: &str) {
+        self.out.push_str(&INDENT.repeat(depth));
+        self.out.push_str(text);
+        self.out.push('\n');
+    }
+
+    fn comments_before(&mut self, offset: usize, depth: usize) {
+        while let Some((span, text)) = self.comments.get(self.next).cloned() {
+            if span.start >= offset {
+                break;
+            }
+
+            self.line(depth, &text);
+            self.next += 1;
+        }
+    }
+
+    fn item(&mut self, depth: usize, text: &str, end: usize) {
+        self.out.push_str(&INDENT.repeat(depth));
+        self.out.push_str(text);
+
+        if let Some((span, comment)) = self.comments.get(self.next) {
+            let between = self.source.get(end..span.start).unwrap_or("\n");
+
+            if between
+                .chars()
+                .all(|ch| ch == ',' || ch == ' ' || ch == '\t')
+            {
+                self.out.push(' ');
+                self.out.push_str(comment);
+                self.next += 1;
+            }
+        }
+
+        self.out.push('\n');
+    }
+
+    fn rule(&mut self, rule: &Rule) {
+        self.comments_before(rule.span.start, 2);
+
+        let mut text = String::new();
+
+        match &rule.items {
+            Some(items) => {
+                for i in items.iter() {
+                    text.push_str(&symbol(i, 2));
+                    text.push(' ');
+                }
+            }
+            None => {
+                for n in rule.nodes.iter() {
+                    text.push_str(&node(n, 2));
+                    text.push(' ');
+                }
+            }
+        }
+
+        text.push_str("=> ");
+        text.push_str(&code(&rule.code, 2));
+        text.push(',');
+
+        self.comments_before(rule.span.end, 2);
+        self.item(2, &text, rule.span.end);
+    }
+
+    fn nonterm(&mut self, def: &NonTermDef, params: &[String]) {
+        let header_end = def
+            .rules
+            .first()
+            .map_or(def.span.end, |rule| rule.span.start);
+        self.comments_before(def.span.start, 1);
+
+        let vis = if def.is_pub { "pub " } else { "" };
+        let ty = match &def.args {
+            Some(args) => format!("{} -> {}", code(args, 1), code(&def.ret_ty, 1)),
+            None => code(&def.ret_ty, 1),
+        };
+
+        let name = match params {
+            [] => def.name.0.clone(),
+            params => format!("{}<{}>", def.name.0, params.join(", ")),
+        };
+
+        self.comments_before(header_end, 1);
+        self.item(1, &format!("{vis}{name}: {ty} = {{"), def.name_span.end);
+
+        for rule in def.rules.iter() {
+            self.rule(rule);
+        }
+
+        self.comments_before(def.span.end, 2);
+        self.line(1, "}");
+    }
+
+    fn tests(&mut self, tests: &Tests) {
+        self.comments_before(tests.span.start, 0);
+        self.line(0, "tests = {");
+
+        for group in tests.groups.iter() {
+            self.group(group);
+        }
+
+        self.comments_before(tests.span.end, 1);
+        self.line(0, "}");
+    }
+
+    fn group(&mut self, group: &TestGroup) {
+        self.comments_before(group.span.start, 1);
+        self.item(1, &format!("{} = {{", group.nonterm.0), group.name_span.end);
+
+        for case in group.cases.iter() {
+            self.comments_before(case.span.start, 2);
+
+            let text = match &case.expect {
+                Expect::Accept => format!("accept {},", literal(&case.input)),
+                Expect::Reject => format!("reject {},", literal(&case.input)),
+                Expect::Value(value) => {
+                    format!("{} => {},", literal(&case.input), code(value, 2))
+                }
+            };
+
+            self.item(2, &text, case.span.end);
+        }
+
+        self.comments_before(group.span.end, 2);
+        self.line(1, "}");
+    }
+
+    fn language(&mut self, language: &Language) {
+        let first_token = Token::lexer(self.source)
+            .spanned()
+            .find(|(token, _)| !matches!(token, Token::Comment(_)))
+            .map_or(0, |(_, span)| span.start);
+
+        self.comments_before(first_token, 0);
+        let preamble = format!("preamble = {}", code(&language.preamble, 0));
+        self.line(0, &preamble);
+        self.out.push('\n');
+
+        self.comments_before(language.lexer.span.start, 0);
+        self.line(0, "tokens = {");
+
+        for (term, tok, span) in language.lexer.mapping.iter() {
+            self.comments_before(span.start, 1);
+
+            self.item(
+                1,
+                &format!("{} = {},", literal(&term.0), desc(tok)),
+                span.end,
+            );
+        }
+
+        self.comments_before(language.lexer.span.end, 1);
+        self.line(0, "}");
+        self.out.push('\n');
+
+        self.comments_before(language.grammar.span.start, 0);
+        self.line(0, "rules = {");
+
+        let mut defs: Vec<(&NonTermDef, &[String])> = language
+            .grammar
+            .nonterms
+            .iter()
+            .filter(|def| def.helper.is_none())
+            .map(|def| (def, &[][..]))
+            .chain(
+                language
+                    .grammar
+                    .macros
+                    .iter()
+                    .map(|m| (&m.def, &m.params[..])),
+            )
+            .collect();
+        defs.sort_by_key(|(def, _)| def.span.start);
+
+        for (i, (def, params)) in defs.into_iter().enumerate() {
+            if i > 0 {
+                self.out.push('\n');
+            }
+
+            self.nonterm(def, params);
+        }
+
+        self.comments_before(language.grammar.span.end, 1);
+        self.line(0, "}");
+
+        if let Some(tests) = &language.tests {
+            self.out.push('\n');
+            self.tests(tests);
+        }
+
+        self.comments_before(usize::MAX, 0);
+    }
+}
+
+pub fn format(language: &Language, source: &str) -> String {
+    let mut formatter = Formatter {
+        source,
+        comments: comments(source),
+        next: 0,
+        out: String::new(),
+    };
+
+    formatter.language(language);
+    formatter.out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{format, parse_language};
+
+    const MESSY: &str = r#"preamble = !{ use std::str::FromStr; }!
+tokens = { "n" = regex "[0-9]+", "+" = token "+",
+  "," = token "," }
+rules = {
+  # a list
+  List<T>: !{Vec<T>}! = { <first:T> <rest:("," <v:T>)*> => !{ let mut v = vec![first]; v.extend(rest); v }!, }
+  num: !{ i32 }! = { <n:"n"> => !{ i32::from_str(n).unwrap() }!, }
+  pub sum: !{ i32 }! = { <first:num> <rest:("+" <v:num>)*> => !{ first + rest.iter().sum::<i32>() }!,
+    <l:List<num>> "+"? => !{ l.len() as i32 }!, }
+}
+tests = { sum = { accept "1+2", reject "+", "1+2" => !{ 3 }!, } }
+"#;
+
+    const FORMATTED: &str = r#"preamble = !{ use std::str::FromStr; }!
+
+tokens = {
+    "n" = regex "[0-9]+",
+    "+" = token "+",
+    "," = token ",",
+}
+
+rules = {
+    # a list
+    List<T>: !{ Vec<T> }! = {
+        <first:T> <rest:("," <v:T>)*> => !{ let mut v = vec![first]; v.extend(rest); v }!,
+    }
+
+    num: !{ i32 }! = {
+        <n:"n"> => !{ i32::from_str(n).unwrap() }!,
+    }
+
+    pub sum: !{ i32 }! = {
+        <first:num> <rest:("+" <v:num>)*> => !{ first + rest.iter().sum::<i32>() }!,
+        <l:List<num>> "+"? => !{ l.len() as i32 }!,
+    }
+}
+
+tests = {
+    sum = {
+        accept "1+2",
+        reject "+",
+        "1+2" => !{ 3 }!,
+    }
+}
+"#;
+
+    #[test]
+    fn formats_messy_source() {
+        assert_eq!(format(MESSY).unwrap(), FORMATTED);
+    }
+
+    #[test]
+    fn formatting_is_stable() {
+        assert_eq!(format(FORMATTED).unwrap(), FORMATTED);
+    }
+
+    #[test]
+    fn keeps_multi_line_strings_in_actions() {
+        let source = r##"preamble = !{ }!
+tokens = { "a" = token "a", }
+rules = {
+  pub text: !{ String }! = { "a" => !{
+        let s = "one
+    two  
+three";
+        let r = r#"four
+  "five"
+"#;
+        format!("{s}{r}") }!, }
+}
+"##;
+        let formatted = r##"preamble = !{ }!
+
+tokens = {
+    "a" = token "a",
+}
+
+rules = {
+    pub text: !{ String }! = {
+        "a" => !{
+            let s = "one
+    two  
+three";
+            let r = r#"four
+  "five"
+"#;
+            format!("{s}{r}")
+        }!,
+    }
+}
+"##;
+
+        assert_eq!(format(source).unwrap(), formatted);
+        assert_eq!(format(formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn formatting_keeps_the_grammar() {
+        let arms = |source: &str| -> Vec<String> {
+            let language = parse_language(source).unwrap();
+            language
+                .grammar
+                .written()
+                .iter()
+                .flat_map(|def| (0..def.rules.len()).map(|r| def.label(r)))
+                .collect()
+        };
+
+        assert_eq!(arms(MESSY), arms(FORMATTED));
+    }
+}
@@ -1,33 +1,155 @@
-use logos::{Logos, SpannedIter};
+use std::{ops::Range, vec::IntoIter};
 
-use crate::notation::tokens::Token;
+use lalrpop_util::ParseError;
+use logos::Logos;
+
+use crate::{ast, notation::tokens::Token};
 
 pub type Spanned<Tok, Loc, Error> = Result<(Loc, Tok, Loc), Error>;
 
 #[derive(Debug)]
 pub enum LexicalError {
-    InvalidToken,
+    InvalidToken { start: usize, end: usize },
 }
 
-pub struct Lexer<'input> {
-    token_stream: SpannedIter<'input, Token>,
+pub struct Lexer {
+    tokens: IntoIter<(Token, Range<usize>)>,
 }
 
-impl<'input> Lexer<'input> {
-    pub fn new(input: &'input str) -> Self {
+// `<` right after a name opens macro arguments (`Sep<expr, ",">`) unless it
+// starts a binding (`<name:...>`), which needs two tokens of lookahead.
+fn is_macro_open(tokens: &[(Token, Range<usize>)], i: usize) -> bool {
+    let kind = |i: usize| tokens.get(i).map(|(token, _)| token);
+
+    matches!(kind(i), Some(Token::LAngle))
+        && i > 0
+        && matches!(kind(i - 1), Some(Token::Identifier(_)))
+        && !(matches!(kind(i + 1), Some(Token::Identifier(_)))
+            && matches!(kind(i + 2), Some(Token::Colon)))
+}
+
+impl Lexer {
+    pub fn new(input: &str) -> Self {
+        let mut tokens: Vec<_> = Token::lexer(input)
+            .spanned()
+            .filter(|(token, _)| !matches!(token, Token::Comment(_)))
+            .collect();
+
+        for i in 0..tokens.len() {
+            if is_macro_open(&tokens, i) {
+                tokens[i].0 = Token::MacroOpen;
+            }
+        }
+
         Self {
-            token_stream: Token::lexer(input).spanned(),
+            tokens: tokens.into_iter(),
         }
     }
 }
 
-impl<'input> Iterator for Lexer<'input> {
+impl Iterator for Lexer {
     type Item = Spanned<Token, usize, LexicalError>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.token_stream.next().map(|(token, span)| match token {
-            Token::Error => Err(LexicalError::InvalidToken),
+        self.tokens.next().map(|(token, span)| match token {
+            Token::Error => Err(LexicalError::InvalidToken {
+                start: span.start,
+                end: span.end,
+            }),
             _ => Ok((span.start, token, span.end)),
         })
     }
 }
+
+// `tests`, `accept` and `reject` are only keywords where the grammar expects
+// them, so they stay usable as nonterminal names.
+pub fn keyword<'k>(
+    (word, span): (String, ast::Span),
+    keywords: &[&'k str],
+) -> Result<&'k str, ParseError<usize, Token, LexicalError>> {
+    match keywords.iter().find(|keyword| **keyword == word) {
+        Some(keyword) => Ok(keyword),
+        None => Err(ParseError::UnrecognizedToken {
+            token: (span.start, Token::Identifier(word), span.end),
+            expected: keywords
+                .iter()
+                .map(|keyword| format!("\"{keyword}\""))
+                .collect(),
+        }),
+    }
+}
+
+pub fn comments(input: &str) -> Vec<(ast::Span, String)> {
+    Token::lexer(input)
+        .spanned()
+        .filter_map(|(token, span)| match token {
+            Token::Comment(text) => Some((
+                ast::Span {
+                    start: span.start,
+                    end: span.end,
+                },
+                text.trim_end().to_owned(),
+            )),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{ast::Expect, parse_language};
+
+    const GRAMMAR: &str = r#"
+preamble = !{ }!
+
+tokens = {
+    "a" = token "a",
+}
+
+rules = {
+    pub tests: !{ () }! = {
+        accept reject => !{ () }!,
+    }
+
+    accept: !{ () }! = {
+        "a" => !{ () }!,
+    }
+
+    reject: !{ () }! = {
+        => !{ () }!,
+    }
+}
+"#;
+
+    #[test]
+    fn keywords_are_contextual() {
+        let tests = r#"
+tests = {
+    tests = {
+        accept "a",
+        reject "",
+    }
+}
+"#;
+        let source = format!("{GRAMMAR}{tests}");
+        let language = parse_language(&source).unwrap();
+        let names: Vec<_> = language
+            .grammar
+            .nonterms
+            .iter()
+            .map(|def| &def.name.0[..])
+            .collect();
+        let cases = &language.tests.unwrap().groups[0].cases;
+
+        assert_eq!(names, ["tests", "accept", "reject"]);
+        assert!(matches!(cases[0].expect, Expect::Accept));
+        assert!(matches!(cases[1].expect, Expect::Reject));
+    }
+
+    #[test]
+    fn misspelled_keywords() {
+        for tests in ["testz = { }", "tests = { tests = { acept \"a\" } }"] {
+            assert!(parse_language(&format!("{GRAMMAR}\n{tests}\n")).is_err());
+        }
+    }
+}
@@ -1,2 +1,8 @@
+use lalrpop_util::lalrpop_mod;
+
+pub mod desugar;
+pub mod format;
 pub mod lexer;
 pub mod tokens;
+
+lalrpop_mod!(#[allow(clippy::all)] pub grammar, "/notation/grammar.rs");
@@ -7,16 +7,39 @@ fn literal(lex: &mut Lexer<Token>) -> String {
     res
 }
 
-fn code(lex: &mut Lexer<Token>) -> String {
+fn code(lex: &mut Lexer<Token>) -> Option<String> {
     while !lex.slice().ends_with("}!") {
-        let byte_len = lex.remainder().chars().next().map_or(1, |ch| ch.len_utf8());
+        let byte_len = lex.remainder().chars().next()?.len_utf8();
         lex.bump(byte_len);
     }
 
     let mut res: String = lex.slice().chars().skip(2).collect();
     res.pop();
     res.pop();
-    res
+    Some(res)
+}
+
+// `( !{ ... }! )` is a single token so that an argument list is told apart
+// from a group right after a nonterminal without more lookahead.
+fn args(lex: &mut Lexer<Token>) -> Option<String> {
+    let start = lex.slice().len();
+
+    while !lex.slice().ends_with("}!") || lex.slice().len() < start + 2 {
+        let byte_len = lex.remainder().chars().next()?.len_utf8();
+        lex.bump(byte_len);
+    }
+
+    let end = lex.slice().len() - 2;
+    let code = lex.slice()[start..end].to_owned();
+    let rest = lex.remainder();
+    let close = rest.len() - rest.trim_start().len();
+
+    if !rest[close..].starts_with(')') {
+        return None;
+    }
+
+    lex.bump(close + 1);
+    Some(code)
 }
 
 #[derive(Logos, Clone, Debug, PartialEq)]
@@ -30,6 +53,9 @@ pub enum Token {
     #[regex("!\\{", code)]
     Code(String),
 
+    #[regex(r"\([ \t\n\f]*!\{", args)]
+    Args(String),
+
     #[token(",")]
     Comma,
 
@@ -53,6 +79,7 @@ pub enum Token {
     RBrace,
     #[token("<")]
     LAngle,
+    MacroOpen,
     #[token(">")]
     RAngle,
 
@@ -65,12 +92,23 @@ pub enum Token {
     #[token("->")]
     TyArrow,
 
+    #[token("?")]
+    Question,
+    #[token("*")]
+    Star,
+    #[token("+")]
+    Plus,
+    #[token("|")]
+    Pipe,
+
     #[token("token")]
     Tok,
     #[token("regex")]
     Reg,
 
-    #[regex(r"#.*\n?", logos::skip)]
+    #[regex(r"#[^\n]*", |lex| lex.slice().to_owned())]
+    Comment(String),
+
     #[regex(r"[ \t\n\f]+", logos::skip)]
     #[error]
     Error,
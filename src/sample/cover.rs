@@ -0,0 +1,230 @@
+use std::collections::HashMap;
+
+use crate::{
+    ast::{Node, NonTerm, Rule},
+    lexer::Term,
+};
+
+use super::{rule_depth, SampleError, Sampler};
+
+pub struct Case<'l> {
+    pub terms: Vec<&'l Term>,
+    pub rules: Vec<(&'l NonTerm, usize)>,
+}
+
+pub struct Coverage<'l> {
+    pub cases: Vec<Case<'l>>,
+    pub uncovered: Vec<(&'l NonTerm, usize)>,
+    pub unused: Vec<&'l Term>,
+}
+
+type Step = (usize, Option<usize>);
+
+struct Cover<'s, 'l> {
+    sampler: &'s Sampler<'l>,
+    index: HashMap<&'l NonTerm, usize>,
+    covered: Vec<Vec<bool>>,
+}
+
+impl<'l> Cover<'_, 'l> {
+    fn rules(&self, def: usize) -> &'l [Rule] {
+        &self.sampler.language.grammar.nonterms[def].rules
+    }
+
+    fn shortest(&self, def: usize) -> usize {
+        let sampler = self.sampler;
+
+        self.rules(def)
+            .iter()
+            .enumerate()
+            .filter_map(|(i, rule)| {
+                let depth = rule_depth(rule, &sampler.depths, &sampler.texts)?;
+                Some(((depth, rule.nodes.len()), i))
+            })
+            .min()
+            .map_or(0, |(_, i)| i)
+    }
+
+    fn choose(&self, def: usize) -> usize {
+        let sampler = self.sampler;
+
+        self.rules(def)
+            .iter()
+            .enumerate()
+            .position(|(i, rule)| {
+                !self.covered[def][i] && rule_depth(rule, &sampler.depths, &sampler.texts).is_some()
+            })
+            .unwrap_or_else(|| self.shortest(def))
+    }
+
+    fn expand(
+        &mut self,
+        def: usize,
+        plan: &[Step],
+        used: &mut Vec<(usize, usize)>,
+        terms: &mut Vec<&'l Term>,
+    ) {
+        let r = match plan.first() {
+            Some(&(r, _)) => r,
+            None => self.choose(def),
+        };
+
+        self.covered[def][r] = true;
+        used.push((def, r));
+
+        for (pos, node) in self.rules(def)[r].nodes.iter().enumerate() {
+            match node {
+                Node::Term(term, _, _) => terms.push(term),
+                Node::NonTerm { node, .. } => {
+                    let rest = match plan.first() {
+                        Some((_, Some(next))) if *next == pos => &plan[1..],
+                        _ => &[],
+                    };
+
+                    self.expand(self.index[node], rest, used, terms);
+                }
+            }
+        }
+    }
+}
+
+impl<'l> Sampler<'l> {
+    pub fn cover(&self, start: &str) -> Result<Coverage<'l>, SampleError> {
+        let start = self.start(start)?;
+        let nonterms = &self.language.grammar.nonterms;
+        let index: HashMap<_, _> = nonterms
+            .iter()
+            .enumerate()
+            .map(|(i, def)| (&def.name, i))
+            .collect();
+
+        let mut lens: Vec<Option<usize>> = vec![None; nonterms.len()];
+        let rule_len = |rule: &Rule, lens: &[Option<usize>]| {
+            rule.nodes.iter().try_fold(0, |len, node| match node {
+                Node::Term(term, _, _) => self.texts.contains_key(term).then_some(len + 1),
+                Node::NonTerm { node, .. } => Some(len + lens[*index.get(node)?]?),
+            })
+        };
+
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (i, def) in nonterms.iter().enumerate() {
+                let len = def
+                    .rules
+                    .iter()
+                    .filter_map(|rule| rule_len(rule, &lens))
+                    .min();
+
+                if len.is_some_and(|len| lens[i].is_none_or(|old| len < old)) {
+                    lens[i] = len;
+                    changed = true;
+                }
+            }
+        }
+
+        let mut dist: Vec<Option<usize>> = vec![None; nonterms.len()];
+        let mut parent: Vec<Option<(usize, usize, usize)>> = vec![None; nonterms.len()];
+        dist[index[start]] = Some(0);
+        changed = true;
+
+        while changed {
+            changed = false;
+
+            for (x, def) in nonterms.iter().enumerate() {
+                let Some(around) = dist[x] else { continue };
+
+                for (r, rule) in def.rules.iter().enumerate() {
+                    let Some(len) = rule_len(rule, &lens) else {
+                        continue;
+                    };
+
+                    for (pos, node) in rule.nodes.iter().enumerate() {
+                        let Node::NonTerm { node, .. } = node else {
+                            continue;
+                        };
+                        let y = index[node];
+                        let cand = around + len - lens[y].unwrap_or(0);
+
+                        if dist[y].is_none_or(|old| cand < old) {
+                            dist[y] = Some(cand);
+                            parent[y] = Some((x, r, pos));
+                            changed = true;
+                        }
+                    }
+                }
+            }
+        }
+
+        let mut cover = Cover {
+            sampler: self,
+            covered: nonterms
+                .iter()
+                .map(|def| vec![false; def.rules.len()])
+                .collect(),
+            index: index.clone(),
+        };
+        let mut cases = Vec::new();
+
+        for (x, def) in nonterms.iter().enumerate() {
+            for (r, rule) in def.rules.iter().enumerate() {
+                if cover.covered[x][r] || dist[x].is_none() || rule_len(rule, &lens).is_none() {
+                    continue;
+                }
+
+                let mut plan = vec![(r, None)];
+                let mut y = x;
+
+                while let Some((px, pr, pos)) = parent[y] {
+                    plan.push((pr, Some(pos)));
+                    y = px;
+                }
+
+                plan.reverse();
+
+                let mut used = Vec::new();
+                let mut terms = Vec::new();
+                cover.expand(cover.index[start], &plan, &mut used, &mut terms);
+
+                used.sort();
+                used.dedup();
+
+                cases.push(Case {
+                    terms,
+                    rules: used
+                        .into_iter()
+                        .map(|(def, r)| (&nonterms[def].name, r))
+                        .collect(),
+                });
+            }
+        }
+
+        let uncovered = nonterms
+            .iter()
+            .enumerate()
+            .flat_map(|(x, def)| {
+                let covered = &cover.covered[x];
+                (0..def.rules.len())
+                    .filter(move |r| !covered[*r])
+                    .map(move |r| (&def.name, r))
+            })
+            .collect();
+
+        let unused = self
+            .language
+            .lexer
+            .mapping
+            .iter()
+            .map(|(term, _, _)| term)
+            .filter(|term| !cases.iter().any(|case| case.terms.contains(term)))
+            .collect();
+
+        Ok(Coverage {
+            cases,
+            uncovered,
+            unused,
+        })
+    }
+}
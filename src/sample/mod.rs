@@ -0,0 +1,231 @@
+use std::{collections::HashMap, fmt};
+
+use regex_syntax::hir::Hir;
+
+use crate::{
+    ast::{Language, Node, NonTerm, NonTermDef, Rule},
+    lexer::{Term, TokDesc},
+};
+
+pub mod cover;
+pub mod regex;
+
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng(seed)
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    pub fn below(&mut self, n: usize) -> usize {
+        if n == 0 {
+            0
+        } else {
+            (self.next_u64() % n as u64) as usize
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SampleError {
+    UnknownNonTerm(NonTerm),
+    Unproductive(NonTerm),
+    InvalidRegex { term: Term, message: String },
+}
+
+impl fmt::Display for SampleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SampleError::UnknownNonTerm(nonterm) => {
+                write!(f, "unknown nonterminal {}", nonterm.0)
+            }
+            SampleError::Unproductive(nonterm) => {
+                write!(f, "{} derives no finite sentence", nonterm.0)
+            }
+            SampleError::InvalidRegex { term, message } => {
+                write!(f, "invalid regex for token \"{}\": {message}", term.0)
+            }
+        }
+    }
+}
+
+enum Text {
+    Literal(String),
+    Regex(Hir),
+}
+
+pub struct Sampler<'l> {
+    language: &'l Language,
+    defs: HashMap<&'l NonTerm, &'l NonTermDef>,
+    depths: HashMap<&'l NonTerm, usize>,
+    texts: HashMap<&'l Term, Text>,
+    max_depth: usize,
+    rng: Rng,
+}
+
+fn rule_depth(
+    rule: &Rule,
+    depths: &HashMap<&NonTerm, usize>,
+    texts: &HashMap<&Term, Text>,
+) -> Option<usize> {
+    rule.nodes.iter().try_fold(0, |depth, node| match node {
+        Node::Term(term, _, _) => texts.contains_key(term).then_some(depth),
+        Node::NonTerm { node, .. } => depths.get(node).map(|d| depth.max(*d)),
+    })
+}
+
+impl<'l> Sampler<'l> {
+    pub fn new(language: &'l Language, max_depth: usize, seed: u64) -> Result<Self, SampleError> {
+        let mut texts = HashMap::new();
+
+        for (term, desc, _) in language.lexer.mapping.iter() {
+            let text = match desc {
+                TokDesc::Token(lit) => Text::Literal(lit.clone()),
+                TokDesc::Regex(re) => {
+                    let re = re.strip_prefix("\\A").unwrap_or(re);
+                    let hir = regex_syntax::parse(re).map_err(|e| SampleError::InvalidRegex {
+                        term: term.clone(),
+                        message: e.to_string(),
+                    })?;
+                    Text::Regex(hir)
+                }
+            };
+
+            texts.insert(term, text);
+        }
+
+        let defs: HashMap<_, _> = language
+            .grammar
+            .nonterms
+            .iter()
+            .map(|def| (&def.name, def))
+            .collect();
+
+        let mut depths = HashMap::new();
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for def in language.grammar.nonterms.iter() {
+                let depth = def
+                    .rules
+                    .iter()
+                    .filter_map(|rule| rule_depth(rule, &depths, &texts))
+                    .min()
+                    .map(|depth| depth + 1);
+
+                if let Some(depth) = depth {
+                    if depths.get(&def.name).is_none_or(|old| depth < *old) {
+                        depths.insert(&def.name, depth);
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        Ok(Sampler {
+            language,
+            defs,
+            depths,
+            texts,
+            max_depth,
+            rng: Rng::new(seed),
+        })
+    }
+
+    fn start(&self, start: &str) -> Result<&'l NonTerm, SampleError> {
+        let start = NonTerm(start.to_owned());
+        let (start, _) = self
+            .defs
+            .get_key_value(&start)
+            .ok_or_else(|| SampleError::UnknownNonTerm(start.clone()))?;
+
+        if !self.depths.contains_key(*start) {
+            return Err(SampleError::Unproductive((*start).clone()));
+        }
+
+        Ok(start)
+    }
+
+    pub fn sentence(&mut self, start: &str) -> Result<String, SampleError> {
+        let start = self.start(start)?;
+        let mut terms = Vec::new();
+        self.derive(start, self.max_depth, &mut terms);
+        Ok(self.render(&terms))
+    }
+
+    pub fn render(&mut self, terms: &[&Term]) -> String {
+        let mut words = Vec::new();
+
+        for term in terms {
+            let word = match &self.texts[term] {
+                Text::Literal(lit) => lit.clone(),
+                Text::Regex(hir) => {
+                    let mut word = String::new();
+                    regex::generate(hir, &mut self.rng, &mut word);
+                    word
+                }
+            };
+
+            if !word.is_empty() {
+                words.push(word);
+            }
+        }
+
+        words.join(" ")
+    }
+
+    fn choose(&mut self, def: &'l NonTermDef, budget: usize) -> &'l Rule {
+        let rules: Vec<_> = def
+            .rules
+            .iter()
+            .filter_map(|rule| Some((rule, rule_depth(rule, &self.depths, &self.texts)?)))
+            .collect();
+
+        let fitting: Vec<_> = rules
+            .iter()
+            .filter(|(_, depth)| *depth < budget)
+            .map(|(rule, _)| *rule)
+            .collect();
+
+        if !fitting.is_empty() {
+            return fitting[self.rng.below(fitting.len())];
+        }
+
+        let shortest = rules
+            .iter()
+            .map(|(rule, depth)| (*depth, rule.nodes.len()))
+            .min();
+        let shortest: Vec<_> = rules
+            .iter()
+            .filter(|(rule, depth)| Some((*depth, rule.nodes.len())) == shortest)
+            .map(|(rule, _)| *rule)
+            .collect();
+
+        shortest[self.rng.below(shortest.len())]
+    }
+
+    fn derive(&mut self, nonterm: &NonTerm, budget: usize, terms: &mut Vec<&'l Term>) {
+        let def = self.defs[nonterm];
+        let rule = self.choose(def, budget);
+
+        for node in rule.nodes.iter() {
+            match node {
+                Node::Term(term, _, _) => terms.push(term),
+                Node::NonTerm { node, .. } => {
+                    self.derive(node, budget.saturating_sub(1), terms);
+                }
+            }
+        }
+    }
+}
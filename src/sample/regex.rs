@@ -0,0 +1,80 @@
+use regex_syntax::hir::{Class, Hir, HirKind};
+
+use super::Rng;
+
+const EXTRA_REPEATS: u32 = 3;
+const PRINTABLE: [(u32, u32); 2] = [(0x21, 0x7e), (0x20, 0x7e)];
+
+fn pick(ranges: &[(u32, u32)], rng: &mut Rng) -> Option<u32> {
+    for (lo, hi) in PRINTABLE {
+        let clipped: Vec<_> = ranges
+            .iter()
+            .map(|&(start, end)| (start.max(lo), end.min(hi)))
+            .filter(|(start, end)| start <= end)
+            .collect();
+        let total: u32 = clipped.iter().map(|(start, end)| end - start + 1).sum();
+
+        if total == 0 {
+            continue;
+        }
+
+        let mut index = rng.below(total as usize) as u32;
+
+        for (start, end) in clipped {
+            let len = end - start + 1;
+
+            if index < len {
+                return Some(start + index);
+            }
+
+            index -= len;
+        }
+    }
+
+    ranges.first().map(|&(start, _)| start)
+}
+
+fn class(class: &Class, rng: &mut Rng, out: &mut String) {
+    let ranges: Vec<_> = match class {
+        Class::Unicode(cls) => cls
+            .ranges()
+            .iter()
+            .map(|range| (range.start() as u32, range.end() as u32))
+            .collect(),
+        Class::Bytes(cls) => cls
+            .ranges()
+            .iter()
+            .map(|range| (range.start() as u32, range.end().min(0x7f) as u32))
+            .filter(|(start, end)| start <= end)
+            .collect(),
+    };
+
+    if let Some(ch) = pick(&ranges, rng).and_then(char::from_u32) {
+        out.push(ch);
+    }
+}
+
+pub fn generate(hir: &Hir, rng: &mut Rng, out: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(lit) => out.push_str(&String::from_utf8_lossy(&lit.0)),
+        HirKind::Class(cls) => class(cls, rng, out),
+        HirKind::Repetition(rep) => {
+            let extra = rep
+                .max
+                .map_or(EXTRA_REPEATS, |max| (max - rep.min).min(EXTRA_REPEATS));
+            let count = rep.min + rng.below(extra as usize + 1) as u32;
+
+            for _ in 0..count {
+                generate(&rep.sub, rng, out);
+            }
+        }
+        HirKind::Capture(capture) => generate(&capture.sub, rng, out),
+        HirKind::Concat(hirs) => {
+            for hir in hirs {
+                generate(hir, rng, out);
+            }
+        }
+        HirKind::Alternation(hirs) => generate(&hirs[rng.below(hirs.len())], rng, out),
+    }
+}
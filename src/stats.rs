@@ -0,0 +1,307 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use serde_json::{json, Value};
+
+use crate::{
+    ast::{FirstMap, Language, Node},
+    diagnostics::Diagnostic,
+    lexer::TokDesc,
+};
+
+#[derive(Debug, Clone)]
+pub struct Stats {
+    pub nonterminals: usize,
+    pub alternatives: usize,
+    pub literal_tokens: usize,
+    pub regex_tokens: usize,
+    pub nullable: usize,
+    pub recursive: usize,
+    pub longest_rule: Option<(String, usize, usize)>,
+    pub max_first: Option<(String, usize)>,
+    pub max_follow: Option<(String, usize)>,
+    pub call_depth: usize,
+    pub generated_fns: usize,
+    pub generated_bytes: Option<usize>,
+}
+
+struct Tarjan<'g> {
+    edges: &'g [Vec<usize>],
+    index: Vec<Option<usize>>,
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    components: Vec<Vec<usize>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, v: usize) {
+        self.index[v] = Some(self.next);
+        self.low[v] = self.next;
+        self.next += 1;
+        self.stack.push(v);
+        self.on_stack[v] = true;
+
+        for &w in self.edges[v].iter() {
+            match self.index[w] {
+                None => {
+                    self.visit(w);
+                    self.low[v] = self.low[v].min(self.low[w]);
+                }
+                Some(index) if self.on_stack[w] => self.low[v] = self.low[v].min(index),
+                Some(_) => {}
+            }
+        }
+
+        if Some(self.low[v]) == self.index[v] {
+            let mut component = Vec::new();
+
+            while let Some(w) = self.stack.pop() {
+                self.on_stack[w] = false;
+                component.push(w);
+
+                if w == v {
+                    break;
+                }
+            }
+
+            self.components.push(component);
+        }
+    }
+}
+
+fn components(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut tarjan = Tarjan {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; edges.len()],
+        next: 0,
+        components: Vec::new(),
+    };
+
+    for v in 0..edges.len() {
+        if tarjan.index[v].is_none() {
+            tarjan.visit(v);
+        }
+    }
+
+    tarjan.components
+}
+
+fn call_graph(language: &Language) -> (usize, usize) {
+    let nonterms = &language.grammar.nonterms;
+    let mut index = HashMap::new();
+
+    for (i, def) in nonterms.iter().enumerate() {
+        index.entry(&def.name).or_insert(i);
+    }
+
+    let edges: Vec<Vec<usize>> = nonterms
+        .iter()
+        .map(|def| {
+            let mut targets: Vec<_> = def
+                .rules
+                .iter()
+                .flat_map(|rule| rule.nodes.iter())
+                .filter_map(|node| match node {
+                    Node::NonTerm { node, .. } => index.get(node).copied(),
+                    Node::Term(..) => None,
+                })
+                .collect();
+            targets.sort();
+            targets.dedup();
+            targets
+        })
+        .collect();
+
+    // Tarjan emits callees before their callers, so one pass computes the depths.
+    let components = components(&edges);
+    let mut component_of = vec![0; nonterms.len()];
+
+    for (c, component) in components.iter().enumerate() {
+        for &v in component {
+            component_of[v] = c;
+        }
+    }
+
+    let written: HashSet<_> = language
+        .grammar
+        .written()
+        .into_iter()
+        .map(|def| &def.name)
+        .collect();
+    let written: Vec<_> = nonterms
+        .iter()
+        .map(|def| written.contains(&def.name))
+        .collect();
+    let mut depth = vec![0; components.len()];
+    let mut recursive = 0;
+
+    // Helpers are called through but only written nonterminals count.
+    for (c, component) in components.iter().enumerate() {
+        let below = component
+            .iter()
+            .flat_map(|&v| edges[v].iter())
+            .map(|&w| component_of[w])
+            .filter(|&d| d != c)
+            .map(|d| depth[d])
+            .max()
+            .unwrap_or(0);
+        depth[c] = below + usize::from(component.iter().any(|&v| written[v]));
+
+        if component.len() > 1 || edges[component[0]].contains(&component[0]) {
+            recursive += component.iter().filter(|&&v| written[v]).count();
+        }
+    }
+
+    let entries = nonterms
+        .iter()
+        .enumerate()
+        .filter(|(_, def)| def.is_pub)
+        .map(|(i, _)| depth[component_of[i]])
+        .max();
+
+    let call_depth = entries.or_else(|| depth.iter().copied().max()).unwrap_or(0);
+    (call_depth, recursive)
+}
+
+fn largest(sets: &FirstMap, language: &Language) -> Option<(String, usize)> {
+    language
+        .grammar
+        .written()
+        .into_iter()
+        .map(|def| {
+            let len = sets.get(&def.name).map_or(0, |set| set.len());
+            (def.name.0.clone(), len)
+        })
+        .rev()
+        .max_by_key(|(_, len)| *len)
+}
+
+impl Stats {
+    pub fn new(language: &Language) -> Stats {
+        let grammar = &language.grammar;
+        let fst = grammar.build_first();
+        let flw = grammar.build_follow(&fst);
+        let (call_depth, recursive) = call_graph(language);
+        let written = grammar.written();
+        let nullable = grammar.nullable();
+
+        let longest_rule = written
+            .iter()
+            .flat_map(|def| {
+                def.rules
+                    .iter()
+                    .enumerate()
+                    .map(move |(r, rule)| (def.name.0.clone(), r + 1, rule.written().len()))
+            })
+            .rev()
+            .max_by_key(|(_, _, len)| *len);
+
+        let literal_tokens = language
+            .lexer
+            .mapping
+            .iter()
+            .filter(|(_, desc, _)| matches!(desc, TokDesc::Token(_)))
+            .count();
+
+        let valid_code = !language
+            .diagnostics()
+            .iter()
+            .any(|diagnostic| matches!(diagnostic, Diagnostic::InvalidCode { .. }));
+
+        Stats {
+            nonterminals: written.len(),
+            alternatives: written.iter().map(|def| def.rules.len()).sum(),
+            literal_tokens,
+            regex_tokens: language.lexer.mapping.len() - literal_tokens,
+            nullable: written
+                .iter()
+                .filter(|def| nullable.contains(&def.name))
+                .count(),
+            recursive,
+            longest_rule,
+            max_first: largest(&fst, language),
+            max_follow: largest(&flw, language),
+            call_depth,
+            generated_fns: grammar.nonterms.len() + 2 * language.lexer.mapping.len(),
+            generated_bytes: valid_code.then(|| language.generate().to_string().len()),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        let set = |set: &Option<(String, usize)>| {
+            set.as_ref()
+                .map(|(nonterm, size)| json!({ "nonterminal": nonterm, "size": size }))
+        };
+
+        json!({
+            "nonterminals": self.nonterminals,
+            "alternatives": self.alternatives,
+            "terminals": self.literal_tokens + self.regex_tokens,
+            "literal_tokens": self.literal_tokens,
+            "regex_tokens": self.regex_tokens,
+            "nullable_nonterminals": self.nullable,
+            "recursive_nonterminals": self.recursive,
+            "longest_rule": self.longest_rule.as_ref().map(|(nonterm, alternative, symbols)| {
+                json!({ "nonterminal": nonterm, "alternative": alternative, "symbols": symbols })
+            }),
+            "max_first": set(&self.max_first),
+            "max_follow": set(&self.max_follow),
+            "call_depth": self.call_depth,
+            "generated_functions": self.generated_fns,
+            "generated_bytes": self.generated_bytes,
+        })
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let set = |set: &Option<(String, usize)>| match set {
+            Some((nonterm, size)) => format!("{size} ({nonterm})"),
+            None => "0".to_owned(),
+        };
+
+        writeln!(f, "nonterminals:           {}", self.nonterminals)?;
+        writeln!(f, "alternatives:           {}", self.alternatives)?;
+        writeln!(
+            f,
+            "terminals:              {} ({} literal, {} regex)",
+            self.literal_tokens + self.regex_tokens,
+            self.literal_tokens,
+            self.regex_tokens
+        )?;
+        writeln!(f, "nullable nonterminals:  {}", self.nullable)?;
+        writeln!(f, "recursive nonterminals: {}", self.recursive)?;
+
+        match &self.longest_rule {
+            Some((nonterm, alternative, symbols)) => writeln!(
+                f,
+                "longest rule:           {symbols} symbols ({nonterm} {alternative})"
+            )?,
+            None => writeln!(f, "longest rule:           0 symbols")?,
+        }
+
+        writeln!(f, "largest FIRST set:      {}", set(&self.max_first))?;
+        writeln!(f, "largest FOLLOW set:     {}", set(&self.max_follow))?;
+        writeln!(f, "call graph depth:       {}", self.call_depth)?;
+
+        match self.generated_bytes {
+            Some(bytes) => writeln!(
+                f,
+                "generated code:         {} functions, ~{bytes} bytes",
+                self.generated_fns
+            ),
+            None => writeln!(
+                f,
+                "generated code:         {} functions, size unknown (invalid code)",
+                self.generated_fns
+            ),
+        }
+    }
+}